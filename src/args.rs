@@ -4,3 +4,10 @@ pub const APPLY_FLAG: noargs::FlagSpec =
 pub const RAW_FLAG: noargs::FlagSpec = noargs::flag("raw")
     .short('r')
     .doc("Output raw JSON response from LSP server");
+
+pub const CONTEXT_OPT: noargs::OptSpec = noargs::opt("context")
+    .short('c')
+    .ty("LINES")
+    .default("5")
+    .env("LSPTERM_CONTEXT_LINES")
+    .doc("Number of lines of context to show around each location");
@@ -0,0 +1,40 @@
+use orfail::OrFail;
+
+use crate::{json::JsonObject, proxy_client::ProxyClient};
+
+/// LSP `Command` (e.g., attached to code actions and code lenses)
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub title: String,
+    pub command: String,
+    pub arguments: Option<nojson::RawJsonOwned>,
+}
+
+impl Command {
+    /// Runs the command on the server side via `workspace/executeCommand`
+    pub fn execute(&self, client: &mut ProxyClient) -> orfail::Result<nojson::RawJsonOwned> {
+        let params = nojson::object(|f| {
+            f.member("command", &self.command)?;
+            if let Some(arguments) = &self.arguments {
+                f.member("arguments", arguments)?;
+            }
+            Ok(())
+        });
+        client.call("workspace/executeCommand", params).or_fail()
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for Command {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            title: object.convert_required("title")?,
+            command: object.convert_required("command")?,
+            arguments: object
+                .get_optional("arguments")
+                .map(|v| v.extract().into_owned()),
+        })
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::{BTreeMap, HashMap};
+
+use orfail::OrFail;
+
+use crate::{
+    json::JsonObject,
+    lsp::{DocumentUri, PositionRange},
+};
+
+/// Proxy-specific request that returns the diagnostics collected by the proxy server
+///
+/// Params: `{"uris"?: DocumentUri[]}` (all documents if omitted)
+/// Result: `{"uri": DocumentUri, "diagnostics": Diagnostic[]}[]`
+pub const GET_DIAGNOSTICS_METHOD: &str = "lspterm/diagnostics";
+
+/// Proxy-specific request that pulls diagnostics from the LSP server and records them in the proxy
+///
+/// Forwarded as `textDocument/diagnostic` if `textDocument` is given and as `workspace/diagnostic`
+/// otherwise, along with the result IDs of the previous pull.
+///
+/// Params: `{"textDocument"?: {"uri": DocumentUri}}`
+/// Result: the result of the forwarded request
+pub const PULL_DIAGNOSTICS_METHOD: &str = "lspterm/pullDiagnostics";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiagnosticSeverity {
+    Error = 1,
+    Warning = 2,
+    Information = 3,
+    Hint = 4,
+}
+
+impl DiagnosticSeverity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Information => "info",
+            Self::Hint => "hint",
+        }
+    }
+}
+
+impl std::str::FromStr for DiagnosticSeverity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Self::Error),
+            "warning" => Ok(Self::Warning),
+            "info" => Ok(Self::Information),
+            "hint" => Ok(Self::Hint),
+            _ => Err(format!(
+                "unknown severity '{s}': expected one of error, warning, info, hint"
+            )),
+        }
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for DiagnosticSeverity {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, nojson::JsonParseError> {
+        match u8::try_from(value)? {
+            1 => Ok(Self::Error),
+            2 => Ok(Self::Warning),
+            3 => Ok(Self::Information),
+            4 => Ok(Self::Hint),
+            _ => Err(value.invalid("unknown diagnostic severity")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub range: PositionRange,
+    pub severity: Option<DiagnosticSeverity>,
+    pub code: Option<String>,
+    pub source: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Returns the severity, treating a missing one as an error
+    pub fn severity(&self) -> DiagnosticSeverity {
+        self.severity.unwrap_or(DiagnosticSeverity::Error)
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for Diagnostic {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        let code = match object.get_optional("code") {
+            Some(code) if code.kind() == nojson::JsonValueKind::String => Some(code.try_into()?),
+            Some(code) if !code.kind().is_null() => Some(code.as_number_str()?.to_owned()),
+            _ => None,
+        };
+        Ok(Self {
+            range: object.convert_required("range")?,
+            severity: object.convert_optional("severity")?,
+            code,
+            source: object.convert_optional("source")?,
+            message: object.convert_required("message")?,
+        })
+    }
+}
+
+/// Diagnostics of a single document returned by [`GET_DIAGNOSTICS_METHOD`]
+#[derive(Debug, Clone)]
+pub struct DocumentDiagnostics {
+    pub uri: DocumentUri,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for DocumentDiagnostics {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            uri: object.convert_required("uri")?,
+            diagnostics: object.convert_required("diagnostics")?,
+        })
+    }
+}
+
+/// Latest diagnostics reported by the LSP server
+///
+/// Both pushed (`textDocument/publishDiagnostics`) and pulled ([`PULL_DIAGNOSTICS_METHOD`])
+/// diagnostics are kept and merged when queried.
+#[derive(Debug, Default)]
+pub struct DiagnosticStore {
+    published: HashMap<DocumentUri, Vec<nojson::RawJsonOwned>>,
+    pulled: HashMap<DocumentUri, PulledDiagnostics>,
+}
+
+#[derive(Debug, Default)]
+struct PulledDiagnostics {
+    result_id: Option<String>,
+    items: Vec<nojson::RawJsonOwned>,
+}
+
+impl DiagnosticStore {
+    pub fn handle_publish_diagnostics(
+        &mut self,
+        params: nojson::RawJsonValue<'_, '_>,
+    ) -> orfail::Result<()> {
+        let object = JsonObject::new(params).or_fail()?;
+        let uri: DocumentUri = object.convert_required("uri").or_fail()?;
+        let diagnostics = to_owned_items(object.get_required("diagnostics").or_fail()?)?;
+        self.published.insert(uri, diagnostics);
+        Ok(())
+    }
+
+    /// Builds the LSP request for [`PULL_DIAGNOSTICS_METHOD`]
+    ///
+    /// The result IDs of the previous pull are included so that the LSP server can reply with
+    /// `unchanged` reports.
+    pub fn build_pull_request(
+        &self,
+        uri: Option<&DocumentUri>,
+    ) -> (&'static str, nojson::RawJsonOwned) {
+        let (method, params) = if let Some(uri) = uri {
+            let result_id = self.pulled.get(uri).and_then(|p| p.result_id.as_ref());
+            let params = nojson::object(|f| {
+                f.member("textDocument", nojson::object(|f| f.member("uri", uri)))?;
+                if let Some(result_id) = result_id {
+                    f.member("previousResultId", result_id)?;
+                }
+                Ok(())
+            });
+            ("textDocument/diagnostic", params.to_string())
+        } else {
+            let ids = nojson::array(|f| {
+                for (uri, pulled) in &self.pulled {
+                    if let Some(result_id) = &pulled.result_id {
+                        f.element(nojson::object(|f| {
+                            f.member("uri", uri)?;
+                            f.member("value", result_id)
+                        }))?;
+                    }
+                }
+                Ok(())
+            });
+            let params = nojson::object(|f| f.member("previousResultIds", &ids));
+            ("workspace/diagnostic", params.to_string())
+        };
+        (method, nojson::RawJsonOwned::parse(params).expect("bug"))
+    }
+
+    /// Records the result of `textDocument/diagnostic`
+    pub fn handle_document_diagnostic_report(
+        &mut self,
+        uri: DocumentUri,
+        report: nojson::RawJsonValue<'_, '_>,
+    ) -> orfail::Result<()> {
+        let object = JsonObject::new(report).or_fail()?;
+        self.handle_report(uri, report).or_fail()?;
+
+        if let Some(related) = object.get_optional("relatedDocuments") {
+            for (uri, report) in related.to_object().or_fail()? {
+                let uri = DocumentUri::try_from(uri).or_fail()?;
+                self.handle_report(uri, report).or_fail()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the result of `workspace/diagnostic`
+    pub fn handle_workspace_diagnostic_report(
+        &mut self,
+        report: nojson::RawJsonValue<'_, '_>,
+    ) -> orfail::Result<()> {
+        let object = JsonObject::new(report).or_fail()?;
+        for item in object
+            .get_required("items")
+            .or_fail()?
+            .to_array()
+            .or_fail()?
+        {
+            let uri = JsonObject::new(item)
+                .or_fail()?
+                .convert_required("uri")
+                .or_fail()?;
+            self.handle_report(uri, item).or_fail()?;
+        }
+        Ok(())
+    }
+
+    fn handle_report(
+        &mut self,
+        uri: DocumentUri,
+        report: nojson::RawJsonValue<'_, '_>,
+    ) -> orfail::Result<()> {
+        let object = JsonObject::new(report).or_fail()?;
+        let kind: String = object.convert_required("kind").or_fail()?;
+        let result_id = object.convert_optional("resultId").or_fail()?;
+        let pulled = self.pulled.entry(uri).or_default();
+        match kind.as_str() {
+            "full" => {
+                pulled.items = to_owned_items(object.get_required("items").or_fail()?)?;
+            }
+            "unchanged" => {}
+            _ => return Err(orfail::Failure::new(format!("unknown report kind: {kind}"))),
+        }
+        pulled.result_id = result_id;
+        Ok(())
+    }
+
+    /// Builds the result of [`GET_DIAGNOSTICS_METHOD`]
+    pub fn get_diagnostics(&self, uris: Option<&[DocumentUri]>) -> nojson::RawJsonOwned {
+        let mut documents = BTreeMap::<_, Vec<_>>::new();
+        let pulled = self.pulled.iter().map(|(uri, p)| (uri, &p.items));
+        for (uri, items) in self.published.iter().chain(pulled) {
+            if uris.is_some_and(|uris| !uris.contains(uri)) {
+                continue;
+            }
+            let merged = documents.entry(uri).or_default();
+            for item in items {
+                // Servers supporting both models may report the same diagnostic twice
+                if !merged.contains(&item) {
+                    merged.push(item);
+                }
+            }
+        }
+
+        let json = nojson::array(|f| {
+            for (uri, diagnostics) in &documents {
+                f.element(nojson::object(|f| {
+                    f.member("uri", uri)?;
+                    f.member("diagnostics", diagnostics)
+                }))?;
+            }
+            Ok(())
+        });
+        nojson::RawJsonOwned::parse(json.to_string()).expect("bug")
+    }
+}
+
+fn to_owned_items(
+    value: nojson::RawJsonValue<'_, '_>,
+) -> orfail::Result<Vec<nojson::RawJsonOwned>> {
+    Ok(value
+        .to_array()
+        .or_fail()?
+        .map(|item| item.extract().into_owned())
+        .collect())
+}
+
+/// Extracts the `uris` parameter of [`GET_DIAGNOSTICS_METHOD`]
+pub fn get_uris(params: &nojson::RawJsonOwned) -> orfail::Result<Option<Vec<DocumentUri>>> {
+    let object = JsonObject::new(params.value()).or_fail()?;
+    object.convert_optional("uris").or_fail()
+}
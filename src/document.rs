@@ -1,20 +1,31 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use orfail::OrFail;
 
 use crate::{
     json::JsonObject,
-    lsp::{DocumentUri, PositionRange},
+    lsp::{DocumentUri, PositionEncoding, PositionRange},
 };
 
-#[derive(Debug, Clone)]
+/// `WorkspaceEdit`
+///
+/// Both the `documentChanges` and the `changes` forms are accepted (the former takes
+/// precedence if both are present), and the changes are kept in the order they must be applied.
+#[derive(Debug, Default, Clone)]
 pub struct DocumentChanges {
     pub changes: Vec<DocumentChange>,
+    pub change_annotations: BTreeMap<String, ChangeAnnotation>,
 }
 
 impl nojson::DisplayJson for DocumentChanges {
     fn fmt(&self, f: &mut nojson::JsonFormatter<'_, '_>) -> std::fmt::Result {
-        f.object(|f| f.member("documentChanges", &self.changes))
+        f.object(|f| {
+            f.member("documentChanges", &self.changes)?;
+            if !self.change_annotations.is_empty() {
+                f.member("changeAnnotations", &self.change_annotations)?;
+            }
+            Ok(())
+        })
     }
 }
 
@@ -24,30 +35,69 @@ impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for DocumentChanges
     fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
         let object = JsonObject::new(value)?;
         let mut changes = Vec::new();
-        for item in object.get_required("documentChanges")?.to_array()? {
-            if let Ok(text_change) = TextDocumentChange::try_from(item) {
-                changes.push(DocumentChange::TextDocument(text_change));
-            } else if let Ok(rename_change) = RenameFileChange::try_from(item) {
-                changes.push(DocumentChange::RenameFile(rename_change));
-            } else {
-                return Err(item.invalid("unknown `documentChanges` entry"));
+        if let Some(document_changes) = object.get_optional("documentChanges") {
+            changes = document_changes.try_into()?;
+        } else if let Some(uri_to_edits) = object.get_optional("changes") {
+            for (uri, edits) in uri_to_edits.to_object()? {
+                changes.push(DocumentChange::TextDocument(TextDocumentChange {
+                    text_document: TextDocument {
+                        uri: uri.try_into()?,
+                        version: None,
+                    },
+                    edits: edits.try_into()?,
+                }));
             }
         }
-        Ok(Self { changes })
+        Ok(Self {
+            changes,
+            change_annotations: object.convert_optional_or_default("changeAnnotations")?,
+        })
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum DocumentChange {
     TextDocument(TextDocumentChange),
+    CreateFile(CreateFileChange),
     RenameFile(RenameFileChange),
+    DeleteFile(DeleteFileChange),
+}
+
+impl DocumentChange {
+    pub fn apply(&self, encoding: PositionEncoding) -> orfail::Result<()> {
+        match self {
+            DocumentChange::TextDocument(change) => change.apply(encoding).or_fail(),
+            DocumentChange::CreateFile(change) => change.apply().or_fail(),
+            DocumentChange::RenameFile(change) => change.apply().or_fail(),
+            DocumentChange::DeleteFile(change) => change.apply().or_fail(),
+        }
+    }
 }
 
 impl nojson::DisplayJson for DocumentChange {
     fn fmt(&self, f: &mut nojson::JsonFormatter<'_, '_>) -> std::fmt::Result {
         match self {
             DocumentChange::TextDocument(change) => change.fmt(f),
+            DocumentChange::CreateFile(change) => change.fmt(f),
             DocumentChange::RenameFile(change) => change.fmt(f),
+            DocumentChange::DeleteFile(change) => change.fmt(f),
+        }
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for DocumentChange {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        let Some(kind) = object.get_optional("kind") else {
+            return Ok(Self::TextDocument(value.try_into()?));
+        };
+        match kind.to_unquoted_string_str()?.as_ref() {
+            "create" => Ok(Self::CreateFile(value.try_into()?)),
+            "rename" => Ok(Self::RenameFile(value.try_into()?)),
+            "delete" => Ok(Self::DeleteFile(value.try_into()?)),
+            _ => Err(kind.invalid("unknown `documentChanges` entry kind")),
         }
     }
 }
@@ -58,6 +108,20 @@ pub struct TextDocumentChange {
     pub edits: Vec<TextEdit>,
 }
 
+impl TextDocumentChange {
+    fn apply(&self, encoding: PositionEncoding) -> orfail::Result<()> {
+        let file_path = self.text_document.uri.path();
+        let content = std::fs::read_to_string(file_path)
+            .or_fail_with(|e| format!("Failed to read file '{}': {}", file_path.display(), e))?;
+
+        let edits = self.edits.iter().collect::<Vec<_>>();
+        let applied_content = apply_text_edits(&content, &edits, encoding).or_fail()?;
+        std::fs::write(file_path, applied_content)
+            .or_fail_with(|e| format!("Failed to write file '{}': {e}", file_path.display()))?;
+        Ok(())
+    }
+}
+
 impl nojson::DisplayJson for TextDocumentChange {
     fn fmt(&self, f: &mut nojson::JsonFormatter<'_, '_>) -> std::fmt::Result {
         f.object(|f| {
@@ -81,22 +145,143 @@ impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for TextDocumentCha
     }
 }
 
+/// Options of `CreateFile` and `RenameFile` (`overwrite` wins over `ignoreIfExists`)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileExistenceOptions {
+    pub overwrite: bool,
+    pub ignore_if_exists: bool,
+}
+
+impl FileExistenceOptions {
+    /// Returns whether the operation should proceed when `path` may already exist
+    fn check(self, path: &std::path::Path) -> orfail::Result<bool> {
+        if !path.exists() || self.overwrite {
+            return Ok(true);
+        }
+        if self.ignore_if_exists {
+            return Ok(false);
+        }
+        Err(orfail::Failure::new(format!(
+            "File '{}' already exists",
+            path.display()
+        )))
+    }
+
+    fn fmt_json(self, f: &mut nojson::JsonObjectFormatter<'_, '_, '_>) -> std::fmt::Result {
+        if self.overwrite || self.ignore_if_exists {
+            f.member(
+                "options",
+                nojson::object(|f| {
+                    f.member("overwrite", self.overwrite)?;
+                    f.member("ignoreIfExists", self.ignore_if_exists)
+                }),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn parse(object: &JsonObject<'_, '_>) -> Result<Self, nojson::JsonParseError> {
+        let Some(options) = object.get_optional("options") else {
+            return Ok(Self::default());
+        };
+        let options = JsonObject::new(options)?;
+        Ok(Self {
+            overwrite: options.convert_optional_or_default("overwrite")?,
+            ignore_if_exists: options.convert_optional_or_default("ignoreIfExists")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateFileChange {
+    pub uri: DocumentUri,
+    pub options: FileExistenceOptions,
+    pub annotation_id: Option<String>,
+}
+
+impl CreateFileChange {
+    pub fn apply(&self) -> orfail::Result<()> {
+        let path = self.uri.path();
+        if !self.options.check(path).or_fail()? {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).or_fail_with(|e| {
+                format!("Failed to create directory '{}': {e}", parent.display())
+            })?;
+        }
+        std::fs::write(path, "")
+            .or_fail_with(|e| format!("Failed to create file '{}': {e}", path.display()))?;
+        Ok(())
+    }
+}
+
+impl nojson::DisplayJson for CreateFileChange {
+    fn fmt(&self, f: &mut nojson::JsonFormatter<'_, '_>) -> std::fmt::Result {
+        f.object(|f| {
+            f.member("kind", "create")?;
+            f.member("uri", &self.uri)?;
+            self.options.fmt_json(f)?;
+            if let Some(id) = &self.annotation_id {
+                f.member("annotationId", id)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for CreateFileChange {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            uri: object.convert_required("uri")?,
+            options: FileExistenceOptions::parse(&object)?,
+            annotation_id: object.convert_optional("annotationId")?,
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RenameFileChange {
     pub old_uri: DocumentUri,
     pub new_uri: DocumentUri,
+    pub options: FileExistenceOptions,
+    pub annotation_id: Option<String>,
 }
 
 impl RenameFileChange {
-    fn apply(&self) -> orfail::Result<()> {
+    pub fn apply(&self) -> orfail::Result<()> {
         let old_path = self.old_uri.path();
         let new_path = self.new_uri.path();
+        if !self.options.check(new_path).or_fail()? {
+            return Ok(());
+        }
 
         if let Some(parent) = new_path.parent() {
             std::fs::create_dir_all(parent).or_fail_with(|e| {
                 format!("Failed to create directory '{}': {e}", parent.display())
             })?;
         }
+        // Overwriting is limited to what `std::fs::rename()` does by itself (replacing a file or
+        // an empty directory), so that a server response can never delete a directory tree
+        let is_dir = std::fs::symlink_metadata(new_path).is_ok_and(|m| m.is_dir());
+        if is_dir {
+            let is_empty = std::fs::read_dir(new_path)
+                .or_fail_with(|e| {
+                    format!("Failed to read directory '{}': {e}", new_path.display())
+                })?
+                .next()
+                .is_none();
+            is_empty.or_fail_with(|()| {
+                format!(
+                    "Cannot overwrite non-empty directory '{}'",
+                    new_path.display()
+                )
+            })?;
+        }
 
         std::fs::rename(old_path, new_path).or_fail_with(|e| {
             format!(
@@ -115,7 +300,12 @@ impl nojson::DisplayJson for RenameFileChange {
         f.object(|f| {
             f.member("kind", "rename")?;
             f.member("oldUri", &self.old_uri)?;
-            f.member("newUri", &self.new_uri)
+            f.member("newUri", &self.new_uri)?;
+            self.options.fmt_json(f)?;
+            if let Some(id) = &self.annotation_id {
+                f.member("annotationId", id)?;
+            }
+            Ok(())
         })
     }
 }
@@ -125,13 +315,122 @@ impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for RenameFileChang
 
     fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
         let object = JsonObject::new(value)?;
-        let kind = object.get_required("kind")?;
-        if kind.to_unquoted_string_str()? != "rename" {
-            return Err(kind.invalid("unsupported kind"));
-        }
         Ok(Self {
             old_uri: object.convert_required("oldUri")?,
             new_uri: object.convert_required("newUri")?,
+            options: FileExistenceOptions::parse(&object)?,
+            annotation_id: object.convert_optional("annotationId")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeleteFileChange {
+    pub uri: DocumentUri,
+    pub recursive: bool,
+    pub ignore_if_not_exists: bool,
+    pub annotation_id: Option<String>,
+}
+
+impl DeleteFileChange {
+    pub fn apply(&self) -> orfail::Result<()> {
+        let path = self.uri.path();
+        if !path.exists() {
+            if self.ignore_if_not_exists {
+                return Ok(());
+            }
+            return Err(orfail::Failure::new(format!(
+                "File '{}' does not exist",
+                path.display()
+            )));
+        }
+
+        let result = if !path.is_dir() {
+            std::fs::remove_file(path)
+        } else if self.recursive {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_dir(path)
+        };
+        result.or_fail_with(|e| format!("Failed to delete '{}': {e}", path.display()))?;
+        Ok(())
+    }
+}
+
+impl nojson::DisplayJson for DeleteFileChange {
+    fn fmt(&self, f: &mut nojson::JsonFormatter<'_, '_>) -> std::fmt::Result {
+        f.object(|f| {
+            f.member("kind", "delete")?;
+            f.member("uri", &self.uri)?;
+            if self.recursive || self.ignore_if_not_exists {
+                f.member(
+                    "options",
+                    nojson::object(|f| {
+                        f.member("recursive", self.recursive)?;
+                        f.member("ignoreIfNotExists", self.ignore_if_not_exists)
+                    }),
+                )?;
+            }
+            if let Some(id) = &self.annotation_id {
+                f.member("annotationId", id)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for DeleteFileChange {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        let options: Option<JsonObject<'_, '_>> = object.convert_optional("options")?;
+        let (recursive, ignore_if_not_exists) = match options {
+            Some(options) => (
+                options.convert_optional_or_default("recursive")?,
+                options.convert_optional_or_default("ignoreIfNotExists")?,
+            ),
+            None => (false, false),
+        };
+        Ok(Self {
+            uri: object.convert_required("uri")?,
+            recursive,
+            ignore_if_not_exists,
+            annotation_id: object.convert_optional("annotationId")?,
+        })
+    }
+}
+
+/// `ChangeAnnotation` referred to by `annotationId`s in a `WorkspaceEdit`
+#[derive(Debug, Clone)]
+pub struct ChangeAnnotation {
+    pub label: String,
+    pub needs_confirmation: bool,
+    pub description: Option<String>,
+}
+
+impl nojson::DisplayJson for ChangeAnnotation {
+    fn fmt(&self, f: &mut nojson::JsonFormatter<'_, '_>) -> std::fmt::Result {
+        f.object(|f| {
+            f.member("label", &self.label)?;
+            f.member("needsConfirmation", self.needs_confirmation)?;
+            if let Some(description) = &self.description {
+                f.member("description", description)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for ChangeAnnotation {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            label: object.convert_required("label")?,
+            needs_confirmation: object.convert_optional_or_default("needsConfirmation")?,
+            description: object.convert_optional("description")?,
         })
     }
 }
@@ -157,22 +456,28 @@ impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for TextDocument {
     fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
         let object = JsonObject::new(value)?;
         let uri = object.convert_required("uri")?;
-        let version = object.convert_required("version")?;
+        let version = object.convert_optional::<Option<u32>>("version")?.flatten();
         Ok(Self { uri, version })
     }
 }
 
+/// `TextEdit` or `AnnotatedTextEdit`
 #[derive(Debug, Clone)]
 pub struct TextEdit {
     pub range: PositionRange,
     pub new_text: String,
+    pub annotation_id: Option<String>,
 }
 
 impl nojson::DisplayJson for TextEdit {
     fn fmt(&self, f: &mut nojson::JsonFormatter<'_, '_>) -> std::fmt::Result {
         f.object(|f| {
             f.member("range", self.range)?;
-            f.member("newText", &self.new_text)
+            f.member("newText", &self.new_text)?;
+            if let Some(id) = &self.annotation_id {
+                f.member("annotationId", id)?;
+            }
+            Ok(())
         })
     }
 }
@@ -184,106 +489,212 @@ impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for TextEdit {
         let object = JsonObject::new(value)?;
         let range = object.convert_required("range")?;
         let new_text = object.convert_required("newText")?;
-        Ok(TextEdit { range, new_text })
+        let annotation_id = object.convert_optional("annotationId")?;
+        Ok(TextEdit {
+            range,
+            new_text,
+            annotation_id,
+        })
     }
 }
 
 impl DocumentChanges {
-    pub fn apply(&self) -> orfail::Result<()> {
-        self.apply_text_document_changes().or_fail()?;
-        self.apply_rename_files_changes().or_fail()?;
-        Ok(())
-    }
-
-    fn apply_text_document_changes(&self) -> orfail::Result<()> {
-        // Group edits by file
-        let mut files_to_edit: HashMap<DocumentUri, Vec<&TextEdit>> = HashMap::new();
+    /// Applies the changes in order (each change refers to the state after the preceding ones)
+    pub fn apply(&self, encoding: PositionEncoding) -> orfail::Result<()> {
         for change in &self.changes {
-            if let DocumentChange::TextDocument(change) = change {
-                for edit in &change.edits {
-                    files_to_edit
-                        .entry(change.text_document.uri.clone())
-                        .or_default()
-                        .push(edit);
-                }
-            }
-        }
-
-        // Apply edits to each file
-        for (uri, mut edits) in files_to_edit {
-            // Convert file:// URI to path
-            let file_path = uri.path();
-
-            // Read file content
-            let content = std::fs::read_to_string(file_path).or_fail_with(|e| {
-                format!("Failed to read file '{}': {}", file_path.display(), e)
-            })?;
-
-            // Sort edits by position (end to start) to avoid offset issues
-            edits.sort_by(|a, b| b.range.start.cmp(&a.range.start));
-
-            let applied_content = self.apply_edits(&content, &edits).or_fail()?;
-            std::fs::write(file_path, applied_content)
-                .or_fail_with(|e| format!("Failed to write file '{}': {e}", file_path.display()))?;
+            change.apply(encoding).or_fail()?;
         }
-
         Ok(())
     }
+}
 
-    fn apply_edits(&self, content: &str, edits: &[&TextEdit]) -> orfail::Result<String> {
-        let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+/// Returns `content` with `edits` applied
+///
+/// As required by the LSP specification, the ranges of `edits` refer to the original `content`
+/// and edits inserting text at the same position are applied in the given order.
+pub fn apply_text_edits(
+    content: &str,
+    edits: &[&TextEdit],
+    encoding: PositionEncoding,
+) -> orfail::Result<String> {
+    let mut byte_edits = Vec::new();
+    for (i, edit) in edits.iter().enumerate() {
+        let (start, end) = edit
+            .range
+            .get_byte_positions(content, encoding)
+            .or_fail_with(|()| format!("invalid edit range: {:?}", edit.range))?;
+        byte_edits.push((start, end, i, edit.new_text.as_str()));
+    }
 
-        for edit in edits {
-            let start_line = edit.range.start.line;
-            let start_char = edit.range.start.character;
-            let end_line = edit.range.end.line;
-            let end_char = edit.range.end.character;
+    // Sort edits by position (end to start) to avoid offset issues
+    byte_edits.sort_by_key(|&(start, end, i, _)| std::cmp::Reverse((start, end, i)));
+
+    let mut new_content = content.to_owned();
+    let mut prev_start = content.len();
+    for (start, end, _, new_text) in byte_edits {
+        (end <= prev_start).or_fail_with(|()| "overlapping text edits".to_owned())?;
+        new_content.replace_range(start..end, new_text);
+        prev_start = start;
+    }
+    Ok(new_content)
+}
 
-            if start_line == end_line {
-                // Single line edit
-                if start_line < lines.len() {
-                    let line = &lines[start_line];
-                    let mut chars: Vec<char> = line.chars().collect();
-                    if start_char <= chars.len() && end_char <= chars.len() {
-                        chars.splice(start_char..end_char, edit.new_text.chars());
-                        lines[start_line] = chars.into_iter().collect::<String>();
+/// Prints `document_changes` as Markdown with a diff for each edit
+pub fn print_markdown_changes(
+    title: &str,
+    document_changes: &DocumentChanges,
+    dry_run: bool,
+    encoding: PositionEncoding,
+) {
+    let base_dir = std::env::current_dir().unwrap_or_default();
+    let annotation_suffix = |id: &Option<String>| {
+        let Some(annotation) = id
+            .as_ref()
+            .and_then(|id| document_changes.change_annotations.get(id))
+        else {
+            return String::new();
+        };
+        let mut suffix = format!(" ({}", annotation.label);
+        if annotation.needs_confirmation {
+            suffix.push_str(", needs confirmation");
+        }
+        suffix.push(')');
+        suffix
+    };
+
+    println!("# {title}{}\n", if dry_run { " (dry-run)" } else { "" });
+
+    for change in &document_changes.changes {
+        match change {
+            DocumentChange::TextDocument(text_change) => {
+                // The file may not exist yet if it is created by a preceding change
+                let text = text_change
+                    .text_document
+                    .uri
+                    .read_to_string()
+                    .unwrap_or_default();
+                let path = text_change.text_document.uri.relative_path(&base_dir);
+
+                println!("## {}\n", path.display());
+
+                for edit in &text_change.edits {
+                    let start = edit.range.start.decode(&text, encoding);
+                    println!(
+                        "### Line {}, Character {}{}\n",
+                        start.line + 1,
+                        start.character + 1,
+                        annotation_suffix(&edit.annotation_id)
+                    );
+
+                    let (start, end) = edit
+                        .range
+                        .get_byte_positions(&text, encoding)
+                        .unwrap_or((text.len(), text.len()));
+                    let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+                    let line_end = text[end..].find('\n').map_or(text.len(), |i| end + i);
+                    let new_lines = format!(
+                        "{}{}{}",
+                        &text[line_start..start],
+                        edit.new_text,
+                        &text[end..line_end]
+                    );
+
+                    println!("```diff");
+                    for old_line in text[line_start..line_end].lines() {
+                        println!("- {old_line}");
                     }
-                }
-            } else {
-                // Multi-line edit
-                if start_line < lines.len() && end_line < lines.len() {
-                    let start_line_content = &lines[start_line];
-                    let end_line_content = &lines[end_line];
-
-                    let start_chars: Vec<char> = start_line_content.chars().collect();
-                    let end_chars: Vec<char> = end_line_content.chars().collect();
-
-                    if start_char <= start_chars.len() && end_char <= end_chars.len() {
-                        let mut new_line = String::new();
-                        new_line.push_str(&start_chars[..start_char].iter().collect::<String>());
-                        new_line.push_str(&edit.new_text);
-                        new_line.push_str(&end_chars[end_char..].iter().collect::<String>());
-
-                        lines.splice(start_line..=end_line, std::iter::once(new_line));
+                    for new_line in new_lines.lines() {
+                        println!("+ {new_line}");
                     }
+                    println!("```\n");
                 }
             }
+            DocumentChange::CreateFile(create_change) => {
+                let path = create_change.uri.relative_path(&base_dir);
+
+                println!(
+                    "## File Creation{}\n",
+                    annotation_suffix(&create_change.annotation_id)
+                );
+                println!("```diff");
+                println!("+ {}", path.display());
+                println!("```\n");
+            }
+            DocumentChange::RenameFile(rename_change) => {
+                let old_path = rename_change.old_uri.relative_path(&base_dir);
+                let new_path = rename_change.new_uri.relative_path(&base_dir);
+
+                println!(
+                    "## File Rename{}\n",
+                    annotation_suffix(&rename_change.annotation_id)
+                );
+                println!("```diff");
+                println!("- {}", old_path.display());
+                println!("+ {}", new_path.display());
+                println!("```\n");
+            }
+            DocumentChange::DeleteFile(delete_change) => {
+                let path = delete_change.uri.relative_path(&base_dir);
+
+                println!(
+                    "## File Deletion{}\n",
+                    annotation_suffix(&delete_change.annotation_id)
+                );
+                println!("```diff");
+                println!("- {}", path.display());
+                println!("```\n");
+            }
         }
+    }
+}
 
-        let mut new_content = lines.join("\n");
-        if content.ends_with('\n') && !new_content.ends_with('\n') {
-            new_content.push('\n');
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::Position;
+
+    fn edit(line: usize, start: usize, end: usize, new_text: &str) -> TextEdit {
+        TextEdit {
+            range: PositionRange {
+                start: Position {
+                    line,
+                    character: start,
+                },
+                end: Position {
+                    line,
+                    character: end,
+                },
+            },
+            new_text: new_text.to_owned(),
+            annotation_id: None,
         }
+    }
 
-        Ok(new_content)
+    #[test]
+    fn apply_edits_with_encoding() {
+        let edits = [edit(0, 3, 4, "c"), edit(1, 0, 0, "// ")];
+        let edits = edits.iter().collect::<Vec<_>>();
+        assert_eq!(
+            apply_text_edits("a😀b\nx\n", &edits, PositionEncoding::Utf16).unwrap(),
+            "a😀c\n// x\n"
+        );
     }
 
-    fn apply_rename_files_changes(&self) -> orfail::Result<()> {
-        for change in &self.changes {
-            if let DocumentChange::RenameFile(change) = change {
-                change.apply().or_fail()?;
-            }
-        }
-        Ok(())
+    #[test]
+    fn apply_inserts_at_same_position_in_order() {
+        let edits = [edit(0, 1, 1, "x"), edit(0, 1, 1, "y"), edit(0, 1, 1, "z")];
+        let edits = edits.iter().collect::<Vec<_>>();
+        assert_eq!(
+            apply_text_edits("a😀b", &edits, PositionEncoding::Utf16).unwrap(),
+            "axyz😀b"
+        );
+    }
+
+    #[test]
+    fn apply_overlapping_edits_fails() {
+        let edits = [edit(0, 0, 2, ""), edit(0, 1, 3, "")];
+        let edits = edits.iter().collect::<Vec<_>>();
+        let error = apply_text_edits("abcd", &edits, PositionEncoding::Utf16).unwrap_err();
+        assert!(error.message.contains("overlapping text edits"));
     }
 }
@@ -0,0 +1,295 @@
+use std::{path::Path, time::SystemTime};
+
+use orfail::OrFail;
+
+use crate::{
+    document::{
+        CreateFileChange, DeleteFileChange, DocumentChanges, RenameFileChange,
+        print_markdown_changes,
+    },
+    glob::GlobPattern,
+    json::JsonObject,
+    lsp::{DocumentUri, PositionEncoding},
+    lsp_server::GET_SERVER_CAPABILITIES_METHOD,
+    proxy_client::ProxyClient,
+};
+
+/// File operation notified to the LSP server via `workspace/will*Files` and `workspace/did*Files`
+#[derive(Debug, Clone)]
+pub enum FileOperation {
+    Create(DocumentUri),
+    Rename(RenameFileChange),
+    Delete(DocumentUri),
+}
+
+impl FileOperation {
+    /// Name of the operation in `ServerCapabilities.workspace.fileOperations` (e.g., `willRename`)
+    fn capability_name(&self, prefix: &str) -> String {
+        let name = match self {
+            Self::Create(_) => "Create",
+            Self::Rename(_) => "Rename",
+            Self::Delete(_) => "Delete",
+        };
+        format!("{prefix}{name}")
+    }
+
+    /// Path matched against the filters of the server's file operation registration options
+    fn filter_target(&self) -> &Path {
+        match self {
+            Self::Create(uri) | Self::Delete(uri) => uri.path(),
+            Self::Rename(change) => change.old_uri.path(),
+        }
+    }
+
+    fn method(&self, prefix: &str) -> String {
+        format!("workspace/{}Files", self.capability_name(prefix))
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Create(_) => "File Creation Changes",
+            Self::Rename(_) => "File Rename Changes",
+            Self::Delete(_) => "File Deletion Changes",
+        }
+    }
+
+    fn done_message(&self) -> &'static str {
+        match self {
+            Self::Create(_) => "Created",
+            Self::Rename(_) => "Renamed",
+            Self::Delete(_) => "Deleted",
+        }
+    }
+
+    fn fmt_params(&self, f: &mut nojson::JsonObjectFormatter<'_, '_, '_>) -> std::fmt::Result {
+        f.member(
+            "files",
+            [nojson::object(|f| match self {
+                Self::Create(uri) | Self::Delete(uri) => f.member("uri", uri),
+                Self::Rename(change) => {
+                    f.member("oldUri", &change.old_uri)?;
+                    f.member("newUri", &change.new_uri)
+                }
+            })],
+        )
+    }
+
+    fn check_preconditions(&self) -> orfail::Result<()> {
+        match self {
+            Self::Create(uri) => {
+                (!uri.path().exists())
+                    .or_fail_with(|()| format!("file '{}' already exists", uri.path().display()))?;
+            }
+            Self::Rename(change) => {
+                change.old_uri.check_existence().or_fail()?;
+                (!change.new_uri.path().exists()).or_fail_with(|()| {
+                    format!("file '{}' already exists", change.new_uri.path().display())
+                })?;
+            }
+            Self::Delete(uri) => {
+                uri.check_existence().or_fail()?;
+                uri.path().is_file().or_fail_with(|()| {
+                    format!("'{}' is not a regular file", uri.path().display())
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&self) -> orfail::Result<()> {
+        match self {
+            Self::Create(uri) => CreateFileChange {
+                uri: uri.clone(),
+                options: Default::default(),
+                annotation_id: None,
+            }
+            .apply()
+            .or_fail(),
+            Self::Rename(change) => change.apply().or_fail(),
+            Self::Delete(uri) => DeleteFileChange {
+                uri: uri.clone(),
+                recursive: false,
+                ignore_if_not_exists: false,
+                annotation_id: None,
+            }
+            .apply()
+            .or_fail(),
+        }
+    }
+
+    fn print_markdown(&self) {
+        let base_dir = std::env::current_dir().unwrap_or_default();
+        let (heading, lines) = match self {
+            Self::Create(uri) => (
+                "File Creation",
+                vec![format!("+ {}", uri.relative_path(&base_dir).display())],
+            ),
+            Self::Rename(change) => (
+                "File Rename",
+                vec![
+                    format!("- {}", change.old_uri.relative_path(&base_dir).display()),
+                    format!("+ {}", change.new_uri.relative_path(&base_dir).display()),
+                ],
+            ),
+            Self::Delete(uri) => (
+                "File Deletion",
+                vec![format!("- {}", uri.relative_path(&base_dir).display())],
+            ),
+        };
+        println!("## {heading}\n");
+        println!("```diff");
+        for line in lines {
+            println!("{line}");
+        }
+        println!("```\n");
+    }
+
+    /// Asks the LSP server for the edits caused by this operation, then (if `apply` is `true`)
+    /// applies them, performs the operation on the file system and notifies the server
+    pub fn run(&self, client: &mut ProxyClient, apply: bool, raw: bool) -> orfail::Result<()> {
+        if let Self::Create(uri) = self
+            && uri.path().is_file()
+        {
+            // Like `touch`, an existing file is not created again and only its mtime is updated
+            if apply {
+                std::fs::File::options()
+                    .append(true)
+                    .open(uri.path())
+                    .and_then(|file| file.set_modified(SystemTime::now()))
+                    .or_fail_with(|e| {
+                        format!(
+                            "failed to update the modification time of '{}': {e}",
+                            uri.path().display()
+                        )
+                    })?;
+                eprintln!("=> Updated the modification time");
+            } else {
+                eprintln!("=> File already exists (--apply only updates its modification time)");
+            }
+            return Ok(());
+        }
+        self.check_preconditions().or_fail()?;
+
+        let capabilities = client.call(GET_SERVER_CAPABILITIES_METHOD, ()).or_fail()?;
+        let target = self.filter_target();
+        let supports_will = is_file_operation_registered(
+            capabilities.value(),
+            &self.capability_name("will"),
+            target,
+        )
+        .or_fail()?;
+        let supports_did = is_file_operation_registered(
+            capabilities.value(),
+            &self.capability_name("did"),
+            target,
+        )
+        .or_fail()?;
+        let encoding = PositionEncoding::from_capabilities(capabilities.value()).or_fail()?;
+
+        let mut document_changes = DocumentChanges::default();
+        if supports_will {
+            let params = nojson::object(|f| self.fmt_params(f));
+            let result = client.call(&self.method("will"), params).or_fail()?;
+            if raw {
+                println!("{result}");
+            }
+            if !result.value().kind().is_null() {
+                document_changes = DocumentChanges::try_from(result.value())
+                    .or_fail_with(|e| format!("Failed to parse document changes: {e}"))?;
+            }
+        }
+
+        if !raw {
+            print_markdown_changes(self.title(), &document_changes, !apply, encoding);
+            self.print_markdown();
+        }
+
+        if apply {
+            // The edits refer to the files before the operation
+            document_changes.apply(encoding).or_fail()?;
+            self.apply().or_fail()?;
+            if supports_did {
+                let params = nojson::object(|f| self.fmt_params(f));
+                client.cast(&self.method("did"), params).or_fail()?;
+            }
+            eprintln!("=> {}", self.done_message());
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether `workspace.fileOperations.{name}` is present in `ServerCapabilities` and
+/// one of its filters matches `path`
+fn is_file_operation_registered(
+    capabilities: nojson::RawJsonValue<'_, '_>,
+    name: &str,
+    path: &Path,
+) -> Result<bool, nojson::JsonParseError> {
+    let Some(workspace) = JsonObject::new(capabilities)?.get_optional("workspace") else {
+        return Ok(false);
+    };
+    let Some(file_operations) = JsonObject::new(workspace)?.get_optional("fileOperations") else {
+        return Ok(false);
+    };
+    let Some(options) = JsonObject::new(file_operations)?.get_optional(name) else {
+        return Ok(false);
+    };
+    let Some(filters) =
+        JsonObject::new(options)?.convert_optional::<Vec<FileOperationFilter>>("filters")?
+    else {
+        // `filters` is required, but a server omitting it presumably wants every operation
+        return Ok(true);
+    };
+    Ok(filters.iter().any(|filter| filter.matches(path)))
+}
+
+/// `FileOperationFilter` of `FileOperationRegistrationOptions`
+#[derive(Debug, Clone)]
+struct FileOperationFilter {
+    scheme: Option<String>,
+    glob: String,
+
+    /// `"file"` or `"folder"` (both if omitted)
+    kind: Option<String>,
+    ignore_case: bool,
+}
+
+impl FileOperationFilter {
+    fn matches(&self, path: &Path) -> bool {
+        if self.scheme.as_ref().is_some_and(|scheme| scheme != "file") {
+            return false;
+        }
+        match self.kind.as_deref() {
+            Some("file") if path.is_dir() => return false,
+            Some("folder") if !path.is_dir() => return false,
+            _ => {}
+        }
+
+        let path = path.to_string_lossy();
+        if self.ignore_case {
+            GlobPattern::new(&self.glob.to_lowercase()).matches(&path.to_lowercase())
+        } else {
+            GlobPattern::new(&self.glob).matches(&path)
+        }
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for FileOperationFilter {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        let pattern: JsonObject = object.convert_required("pattern")?;
+        let ignore_case = match pattern.get_optional("options") {
+            Some(options) => JsonObject::new(options)?.convert_optional_or_default("ignoreCase")?,
+            None => false,
+        };
+        Ok(Self {
+            scheme: object.convert_optional("scheme")?,
+            glob: pattern.convert_required("glob")?,
+            kind: pattern.convert_optional("matches")?,
+            ignore_case,
+        })
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, mpsc::Sender},
+    time::{Duration, SystemTime},
+};
+
+use orfail::OrFail;
+
+use crate::{glob::GlobPattern, json::JsonObject, lsp::DocumentUri, lsp_server::LspMessage};
+
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// VCS metadata directories, which are never scanned
+const VCS_DIRS: &[&str] = &[".git", ".hg", ".svn"];
+
+/// Build output and dependency directories, which are not scanned at the workspace root
+const BUILD_DIRS: &[&str] = &["target", "node_modules"];
+
+const WATCH_KIND_CREATE: u32 = 1;
+const WATCH_KIND_CHANGE: u32 = 2;
+const WATCH_KIND_DELETE: u32 = 4;
+
+const FILE_CHANGE_TYPE_CREATED: u32 = 1;
+const FILE_CHANGE_TYPE_CHANGED: u32 = 2;
+const FILE_CHANGE_TYPE_DELETED: u32 = 3;
+
+/// `workspace/didChangeWatchedFiles` watchers registered by the LSP server, keyed by registration ID
+#[derive(Debug, Default, Clone)]
+pub struct WatcherRegistry(Arc<Mutex<HashMap<String, Vec<FileSystemWatcher>>>>);
+
+impl WatcherRegistry {
+    /// Handles `client/registerCapability` params
+    pub fn register(&self, params: nojson::RawJsonValue<'_, '_>) -> orfail::Result<()> {
+        let object = JsonObject::new(params).or_fail()?;
+        for registration in object
+            .get_required("registrations")
+            .or_fail()?
+            .to_array()
+            .or_fail()?
+        {
+            let registration = JsonObject::new(registration).or_fail()?;
+            let method: String = registration.convert_required("method").or_fail()?;
+            if method != "workspace/didChangeWatchedFiles" {
+                continue;
+            }
+
+            let id: String = registration.convert_required("id").or_fail()?;
+            let options: JsonObject = registration.convert_required("registerOptions").or_fail()?;
+            let watchers = options.convert_required("watchers").or_fail()?;
+            self.0.lock().or_fail()?.insert(id, watchers);
+        }
+        Ok(())
+    }
+
+    /// Handles `client/unregisterCapability` params
+    pub fn unregister(&self, params: nojson::RawJsonValue<'_, '_>) -> orfail::Result<()> {
+        let object = JsonObject::new(params).or_fail()?;
+        // NOTE: "unregisterations" is a well-known typo in the LSP specification
+        for unregistration in object
+            .get_required("unregisterations")
+            .or_fail()?
+            .to_array()
+            .or_fail()?
+        {
+            let unregistration = JsonObject::new(unregistration).or_fail()?;
+            let id: String = unregistration.convert_required("id").or_fail()?;
+            self.0.lock().or_fail()?.remove(&id);
+        }
+        Ok(())
+    }
+
+    fn watch_kind(&self, workspace_dir: &Path, path: &Path) -> orfail::Result<u32> {
+        let registrations = self.0.lock().or_fail()?;
+        let kind = registrations
+            .values()
+            .flatten()
+            .filter(|watcher| watcher.matches(workspace_dir, path))
+            .fold(0, |acc, watcher| acc | watcher.kind);
+        Ok(kind)
+    }
+
+    fn is_empty(&self) -> orfail::Result<bool> {
+        Ok(self.0.lock().or_fail()?.is_empty())
+    }
+
+    fn watchers(&self) -> orfail::Result<Vec<FileSystemWatcher>> {
+        Ok(self
+            .0
+            .lock()
+            .or_fail()?
+            .values()
+            .flatten()
+            .cloned()
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileSystemWatcher {
+    pub base_dir: Option<PathBuf>,
+    pub pattern: GlobPattern,
+    pub kind: u32,
+}
+
+impl FileSystemWatcher {
+    fn matches(&self, workspace_dir: &Path, path: &Path) -> bool {
+        let target = if let Some(base_dir) = &self.base_dir {
+            match path.strip_prefix(base_dir) {
+                Ok(relative) => relative,
+                Err(_) => return false,
+            }
+        } else if self.pattern.is_absolute() {
+            path
+        } else {
+            path.strip_prefix(workspace_dir).unwrap_or(path)
+        };
+        self.pattern.matches(&target.to_string_lossy())
+    }
+
+    /// Returns `true` if some file under `dir` may match this watcher
+    fn may_match_under(&self, workspace_dir: &Path, dir: &Path) -> bool {
+        let target = if let Some(base_dir) = &self.base_dir {
+            match dir.strip_prefix(base_dir) {
+                Ok(relative) => relative,
+                Err(_) => return base_dir.starts_with(dir),
+            }
+        } else if self.pattern.is_absolute() {
+            dir
+        } else {
+            dir.strip_prefix(workspace_dir).unwrap_or(dir)
+        };
+        if target.as_os_str().is_empty() {
+            return true;
+        }
+        self.pattern.may_match_under(&target.to_string_lossy())
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for FileSystemWatcher {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        let glob_pattern = object.get_required("globPattern")?;
+        let kind = object
+            .convert_optional("kind")?
+            .unwrap_or(WATCH_KIND_CREATE | WATCH_KIND_CHANGE | WATCH_KIND_DELETE);
+
+        if glob_pattern.kind() == nojson::JsonValueKind::String {
+            let pattern: String = glob_pattern.try_into()?;
+            return Ok(Self {
+                base_dir: None,
+                pattern: GlobPattern::new(&pattern),
+                kind,
+            });
+        }
+
+        // RelativePattern
+        let relative_pattern = JsonObject::new(glob_pattern)?;
+        let base_uri = relative_pattern.get_required("baseUri")?;
+        let base_uri: DocumentUri = if base_uri.kind() == nojson::JsonValueKind::Object {
+            // WorkspaceFolder
+            JsonObject::new(base_uri)?.convert_required("uri")?
+        } else {
+            base_uri.try_into()?
+        };
+        let pattern: String = relative_pattern.convert_required("pattern")?;
+        Ok(Self {
+            base_dir: Some(base_uri.path().to_path_buf()),
+            pattern: GlobPattern::new(&pattern),
+            kind,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileState {
+    mtime: Option<SystemTime>,
+    len: u64,
+}
+
+/// Polls the workspace folder and sends `workspace/didChangeWatchedFiles` for files matching the registered watchers
+#[derive(Debug)]
+pub struct FileWatcher {
+    workspace_dir: PathBuf,
+    registry: WatcherRegistry,
+    message_tx: Sender<LspMessage>,
+    snapshot: Option<HashMap<PathBuf, FileState>>,
+}
+
+impl FileWatcher {
+    pub fn new(
+        workspace_folder_uri: &DocumentUri,
+        registry: WatcherRegistry,
+        message_tx: Sender<LspMessage>,
+    ) -> Self {
+        Self {
+            workspace_dir: workspace_folder_uri.path().to_path_buf(),
+            registry,
+            message_tx,
+            snapshot: None,
+        }
+    }
+
+    pub fn run(mut self) -> orfail::Result<()> {
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+
+            if self.registry.is_empty().or_fail()? {
+                self.snapshot = None;
+                continue;
+            }
+
+            let watchers = self.registry.watchers().or_fail()?;
+            let should_scan = |dir: &Path| {
+                let is_build_dir = dir.parent() == Some(self.workspace_dir.as_path())
+                    && dir
+                        .file_name()
+                        .is_some_and(|name| BUILD_DIRS.iter().any(|build| name == *build));
+                !is_build_dir
+                    && watchers
+                        .iter()
+                        .any(|watcher| watcher.may_match_under(&self.workspace_dir, dir))
+            };
+            let mut current = HashMap::new();
+            scan_dir(&self.workspace_dir, &mut current, &should_scan);
+            let Some(previous) = self.snapshot.replace(current) else {
+                continue;
+            };
+            let current = self.snapshot.as_ref().expect("infallible");
+
+            let mut changes = Vec::new();
+            for (path, state) in current {
+                match previous.get(path) {
+                    None => changes.push((path, FILE_CHANGE_TYPE_CREATED, WATCH_KIND_CREATE)),
+                    Some(prev) if prev != state => {
+                        changes.push((path, FILE_CHANGE_TYPE_CHANGED, WATCH_KIND_CHANGE))
+                    }
+                    Some(_) => {}
+                }
+            }
+            for path in previous.keys() {
+                if !current.contains_key(path) {
+                    changes.push((path, FILE_CHANGE_TYPE_DELETED, WATCH_KIND_DELETE));
+                }
+            }
+
+            let mut events = Vec::new();
+            for (path, change_type, watch_kind) in changes {
+                if self
+                    .registry
+                    .watch_kind(&self.workspace_dir, path)
+                    .or_fail()?
+                    & watch_kind
+                    != 0
+                {
+                    events.push((DocumentUri::new(path).or_fail()?, change_type));
+                }
+            }
+            if events.is_empty() {
+                continue;
+            }
+            events.sort();
+
+            let params = nojson::object(|f| {
+                f.member(
+                    "changes",
+                    nojson::array(|f| {
+                        for (uri, change_type) in &events {
+                            f.element(nojson::object(|f| {
+                                f.member("uri", uri)?;
+                                f.member("type", *change_type)
+                            }))?;
+                        }
+                        Ok(())
+                    }),
+                )
+            });
+            let params = nojson::RawJsonOwned::parse(params.to_string()).or_fail()?;
+            let msg = LspMessage::Notification {
+                method: "workspace/didChangeWatchedFiles".to_owned(),
+                params: Some(params),
+            };
+            if self.message_tx.send(msg).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn scan_dir(
+    dir: &Path,
+    states: &mut HashMap<PathBuf, FileState>,
+    should_scan: &dyn Fn(&Path) -> bool,
+) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            let is_vcs_dir = VCS_DIRS.iter().any(|name| entry.file_name() == *name);
+            if !is_vcs_dir && should_scan(&path) {
+                scan_dir(&path, states, should_scan);
+            }
+        } else if let Ok(metadata) = entry.metadata() {
+            let state = FileState {
+                mtime: metadata.modified().ok(),
+                len: metadata.len(),
+            };
+            states.insert(path, state);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+/// Glob pattern as defined by the LSP specification
+///
+/// Supports `*`, `?`, `**`, `{a,b}` and `[a-z]` / `[!a-z]`.
+#[derive(Debug, Clone)]
+pub struct GlobPattern {
+    /// Brace-expanded alternatives, each split into `/`-separated segments
+    alternatives: Vec<Vec<String>>,
+}
+
+impl GlobPattern {
+    pub fn new(pattern: &str) -> Self {
+        let alternatives = expand_braces(pattern)
+            .into_iter()
+            .map(|p| p.split('/').map(|s| s.to_owned()).collect())
+            .collect();
+        Self { alternatives }
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        self.alternatives
+            .iter()
+            .all(|segments| segments.first().is_some_and(|s| s.is_empty()))
+    }
+
+    /// Returns `true` if the `/`-separated `path` matches this pattern
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path.split('/').collect::<Vec<_>>();
+        self.alternatives
+            .iter()
+            .any(|segments| match_segments(segments, &path))
+    }
+
+    /// Returns `true` if some path under the `/`-separated directory `dir` may match this pattern
+    pub fn may_match_under(&self, dir: &str) -> bool {
+        let dir = dir.split('/').collect::<Vec<_>>();
+        self.alternatives
+            .iter()
+            .any(|segments| match_prefix(segments, &dir))
+    }
+}
+
+fn match_prefix(pattern: &[String], dir: &[&str]) -> bool {
+    let Some((segment, dir_rest)) = dir.split_first() else {
+        // Paths under `dir` have at least one more segment
+        return !pattern.is_empty();
+    };
+    let Some((first, rest)) = pattern.split_first() else {
+        return false;
+    };
+    if first == "**" {
+        return true;
+    }
+    let pattern_chars = first.chars().collect::<Vec<_>>();
+    let segment_chars = segment.chars().collect::<Vec<_>>();
+    match_segment(&pattern_chars, &segment_chars) && match_prefix(rest, dir_rest)
+}
+
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_owned()];
+    };
+
+    // Find the matching close brace and the top-level commas within it
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    let mut close = None;
+    for (i, c) in pattern[open..].char_indices().map(|(i, c)| (i + open, c)) {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(i);
+                    break;
+                }
+            }
+            ',' if depth == 1 => commas.push(i),
+            _ => {}
+        }
+    }
+    let Some(close) = close else {
+        return vec![pattern.to_owned()];
+    };
+
+    let prefix = &pattern[..open];
+    let suffixes = expand_braces(&pattern[close + 1..]);
+    let mut bounds = vec![open];
+    bounds.extend(commas);
+    bounds.push(close);
+
+    let mut expanded = Vec::new();
+    for window in bounds.windows(2) {
+        for middle in expand_braces(&pattern[window[0] + 1..window[1]]) {
+            for suffix in &suffixes {
+                expanded.push(format!("{prefix}{middle}{suffix}"));
+            }
+        }
+    }
+    expanded
+}
+
+fn match_segments(pattern: &[String], path: &[&str]) -> bool {
+    let Some((first, rest)) = pattern.split_first() else {
+        return path.is_empty();
+    };
+
+    if first == "**" {
+        return (0..=path.len()).any(|i| match_segments(rest, &path[i..]));
+    }
+
+    let Some((segment, path_rest)) = path.split_first() else {
+        return false;
+    };
+    let pattern_chars = first.chars().collect::<Vec<_>>();
+    let segment_chars = segment.chars().collect::<Vec<_>>();
+    match_segment(&pattern_chars, &segment_chars) && match_segments(rest, path_rest)
+}
+
+fn match_segment(pattern: &[char], text: &[char]) -> bool {
+    let Some((&p, pattern_rest)) = pattern.split_first() else {
+        return text.is_empty();
+    };
+
+    match p {
+        '*' => (0..=text.len()).any(|i| match_segment(pattern_rest, &text[i..])),
+        '?' => !text.is_empty() && match_segment(pattern_rest, &text[1..]),
+        '[' => {
+            let Some(close) = pattern
+                .iter()
+                .skip(2)
+                .position(|&c| c == ']')
+                .map(|i| i + 2)
+            else {
+                return text.first() == Some(&'[') && match_segment(pattern_rest, &text[1..]);
+            };
+            let Some((&c, text_rest)) = text.split_first() else {
+                return false;
+            };
+            match_char_class(&pattern[1..close], c)
+                && match_segment(&pattern[close + 1..], text_rest)
+        }
+        _ => text.first() == Some(&p) && match_segment(pattern_rest, &text[1..]),
+    }
+}
+
+fn match_char_class(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.split_first() {
+        Some(('!', rest)) => (true, rest),
+        _ => (false, class),
+    };
+
+    let mut matched = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            matched |= (class[i]..=class[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= class[i] == c;
+            i += 1;
+        }
+    }
+    matched != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn double_star() {
+        let pattern = GlobPattern::new("**/*.rs");
+        assert!(pattern.matches("main.rs"));
+        assert!(pattern.matches("src/main.rs"));
+        assert!(pattern.matches("src/a/b/lib.rs"));
+        assert!(!pattern.matches("src/main.rs.bak"));
+
+        let pattern = GlobPattern::new("src/**/mod.rs");
+        assert!(pattern.matches("src/mod.rs"));
+        assert!(pattern.matches("src/a/b/mod.rs"));
+        assert!(!pattern.matches("tests/mod.rs"));
+    }
+
+    #[test]
+    fn braces() {
+        let pattern = GlobPattern::new("**/*.{ts,js}");
+        assert!(pattern.matches("a/b.ts"));
+        assert!(pattern.matches("b.js"));
+        assert!(!pattern.matches("b.rs"));
+
+        let pattern = GlobPattern::new("{src,tests/{unit,e2e}}/*.rs");
+        assert!(pattern.matches("src/a.rs"));
+        assert!(pattern.matches("tests/e2e/a.rs"));
+        assert!(!pattern.matches("tests/a.rs"));
+    }
+
+    #[test]
+    fn char_classes() {
+        let pattern = GlobPattern::new("file[0-9].txt");
+        assert!(pattern.matches("file1.txt"));
+        assert!(!pattern.matches("filea.txt"));
+
+        let pattern = GlobPattern::new("file[!0-9].txt");
+        assert!(pattern.matches("filea.txt"));
+        assert!(!pattern.matches("file1.txt"));
+
+        let pattern = GlobPattern::new("?[ab]");
+        assert!(pattern.matches("xa"));
+        assert!(!pattern.matches("xc"));
+        assert!(!pattern.matches("a"));
+    }
+
+    #[test]
+    fn relative_and_absolute() {
+        // Relative patterns match paths relative to their base directory
+        let pattern = GlobPattern::new("*.toml");
+        assert!(!pattern.is_absolute());
+        assert!(pattern.matches("Cargo.toml"));
+        assert!(!pattern.matches("sub/Cargo.toml"));
+
+        let pattern = GlobPattern::new("/tmp/**/*.json");
+        assert!(pattern.is_absolute());
+        assert!(pattern.matches("/tmp/a/b.json"));
+        assert!(!pattern.matches("tmp/a/b.json"));
+    }
+
+    #[test]
+    fn may_match_under() {
+        let pattern = GlobPattern::new("src/**/*.rs");
+        assert!(pattern.may_match_under("src"));
+        assert!(pattern.may_match_under("src/a/b"));
+        assert!(!pattern.may_match_under("target"));
+
+        let pattern = GlobPattern::new("*.toml");
+        assert!(!pattern.may_match_under("src"));
+
+        let pattern = GlobPattern::new("{docs,src}/*.md");
+        assert!(pattern.may_match_under("docs"));
+        assert!(!pattern.may_match_under("docs/sub"));
+    }
+}
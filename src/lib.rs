@@ -1,14 +1,44 @@
 pub mod args;
+pub mod command;
+pub mod diagnostic;
 pub mod document;
+pub mod file_operation;
+pub mod file_watcher;
+pub mod glob;
 pub mod json;
+pub mod location;
 pub mod lsp;
 pub mod lsp_server;
+pub mod open_documents;
+pub mod progress;
 pub mod proxy_client;
 pub mod proxy_server;
 pub mod subcommand_act;
+pub mod subcommand_calls;
+pub mod subcommand_code_lens;
 pub mod subcommand_completion;
+pub mod subcommand_declaration;
 pub mod subcommand_definition;
+pub mod subcommand_diagnostics;
+pub mod subcommand_folding;
+pub mod subcommand_format;
+pub mod subcommand_highlight;
 pub mod subcommand_hover;
+pub mod subcommand_implementation;
+pub mod subcommand_inlay_hints;
+pub mod subcommand_links;
+pub mod subcommand_mv;
+pub mod subcommand_outline;
+pub mod subcommand_references;
 pub mod subcommand_rename;
+pub mod subcommand_rm;
+pub mod subcommand_selection_range;
+pub mod subcommand_semantic_tokens;
 pub mod subcommand_serve;
+pub mod subcommand_signature;
+pub mod subcommand_symbols;
+pub mod subcommand_touch;
+pub mod subcommand_type_definition;
+pub mod subcommand_types;
+pub mod symbol;
 pub mod target;
@@ -0,0 +1,211 @@
+use std::num::NonZeroUsize;
+
+use orfail::OrFail;
+
+use crate::{
+    args::{CONTEXT_OPT, RAW_FLAG},
+    json::JsonObject,
+    lsp::{DocumentUri, PositionEncoding, PositionRange},
+    proxy_client::{PORT_OPT, ProxyClient},
+    target::{TARGET_ARG, TargetLocation},
+};
+
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub uri: DocumentUri,
+    pub range: PositionRange,
+}
+
+impl From<LocationLink> for Location {
+    fn from(link: LocationLink) -> Self {
+        Self {
+            uri: link.target_uri,
+            range: link.target_selection_range,
+        }
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for Location {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            uri: object.convert_required("uri")?,
+            range: object.convert_required("range")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LocationLink {
+    pub origin_selection_range: Option<PositionRange>,
+    pub target_uri: DocumentUri,
+    pub target_range: PositionRange,
+    pub target_selection_range: PositionRange,
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for LocationLink {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            origin_selection_range: object.convert_optional("originSelectionRange")?,
+            target_uri: object.convert_required("targetUri")?,
+            target_range: object.convert_required("targetRange")?,
+            target_selection_range: object.convert_required("targetSelectionRange")?,
+        })
+    }
+}
+
+/// Result of navigation requests such as `textDocument/definition`
+/// (`Location | Location[] | LocationLink[] | null`)
+#[derive(Debug, Default, Clone)]
+pub struct Locations(pub Vec<Location>);
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for Locations {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        if value.kind().is_null() {
+            return Ok(Self::default());
+        }
+        if value.kind() == nojson::JsonValueKind::Object {
+            return Ok(Self(vec![value.try_into()?]));
+        }
+
+        let mut locations = Vec::new();
+        for item in value.to_array()? {
+            if item.to_member("targetUri")?.get().is_some() {
+                locations.push(LocationLink::try_from(item)?.into());
+            } else {
+                locations.push(item.try_into()?);
+            }
+        }
+        Ok(Self(locations))
+    }
+}
+
+/// Prints each location as a `## {title} N: ...` section with the surrounding source lines
+pub fn print_locations(
+    title: &str,
+    locations: &[Location],
+    context_lines: usize,
+    encoding: PositionEncoding,
+) -> orfail::Result<()> {
+    for (i, location) in locations.iter().enumerate() {
+        let text = location.uri.read_to_string().or_fail()?;
+        let selection_text = location.range.get_range_text(&text, encoding).or_fail()?;
+
+        println!("## {title} {}: `{selection_text}`", i + 1);
+        println!();
+        print_source_excerpt(
+            &location.uri,
+            &text,
+            location.range,
+            context_lines,
+            encoding,
+            false,
+        )
+        .or_fail()?;
+        println!();
+    }
+    Ok(())
+}
+
+/// Prints `path:line:char:` followed by a code block showing the lines around `range`
+///
+/// If `underline` is `true`, the part of the start line covered by `range` is marked with `^^^`.
+pub fn print_source_excerpt(
+    uri: &DocumentUri,
+    text: &str,
+    range: PositionRange,
+    context_lines: usize,
+    encoding: PositionEncoding,
+    underline: bool,
+) -> orfail::Result<()> {
+    let range = range.decode(text, encoding);
+    let base_dir = std::env::current_dir().or_fail()?;
+    println!(
+        "{}:{}:{}:",
+        uri.relative_path(&base_dir).display(),
+        range.start.line + 1,
+        range.start.character + 1
+    );
+
+    println!("```");
+    let lines = text.lines().collect::<Vec<_>>();
+    for line in range.start.line.saturating_sub(context_lines)..=range.end.line + context_lines {
+        let Some(line_str) = lines.get(line) else {
+            continue;
+        };
+
+        println!(
+            "{} {line_str}",
+            if line == range.start.line { '>' } else { ' ' },
+        );
+        if underline && line == range.start.line {
+            println!("  {}", underline_marker(line_str, range));
+        }
+    }
+    println!("```");
+    Ok(())
+}
+
+/// Returns a `^^^` marker aligned with the part of `line` covered by the decoded `range`
+fn underline_marker(line: &str, range: PositionRange) -> String {
+    let len = line.chars().count();
+    let start = range.start.character.min(len);
+    let end = if range.end.line == range.start.line {
+        range.end.character.clamp(start, len)
+    } else {
+        len
+    };
+    format!("{}{}", " ".repeat(start), "^".repeat((end - start).max(1)))
+}
+
+/// Common implementation of the subcommands that send a `TargetLocation` and
+/// print the resulting locations (e.g., `definition`)
+pub fn try_run_subcommand(
+    mut args: noargs::RawArgs,
+    cmd: noargs::CmdSpec,
+    method: &str,
+    title: &str,
+) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !cmd.take(&mut args).is_present() {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let context_lines: NonZeroUsize = CONTEXT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let target: TargetLocation = TARGET_ARG.take(&mut args).then(|a| a.value().parse())?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    target.file.check_existence().or_fail()?;
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    let text = target.file.read_to_string().or_fail()?;
+    let params = nojson::object(|f| target.fmt_json_object(f, &text, encoding));
+    let result = client.call(method, params).or_fail()?;
+
+    if raw {
+        println!("{result}");
+        return Ok(None);
+    }
+
+    let locations = Locations::try_from(result.value()).or_fail()?;
+    if locations.0.is_empty() {
+        println!("Not found");
+        return Ok(None);
+    }
+
+    print_locations(title, &locations.0, context_lines.get(), encoding).or_fail()?;
+    Ok(None)
+}
@@ -188,12 +188,120 @@ impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for DocumentUri {
     }
 }
 
+/// Unit of `Position.character` negotiated with the LSP server (`PositionEncodingKind`)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf16 => "utf-16",
+            Self::Utf32 => "utf-32",
+        }
+    }
+
+    /// Returns `ServerCapabilities.positionEncoding` (UTF-16 if omitted, as the specification requires)
+    pub fn from_capabilities(
+        capabilities: nojson::RawJsonValue<'_, '_>,
+    ) -> Result<Self, nojson::JsonParseError> {
+        Ok(JsonObject::new(capabilities)?
+            .convert_optional("positionEncoding")?
+            .unwrap_or_default())
+    }
+
+    /// Returns the byte offset in `s` at `units` code units (e.g., offsets within a label)
+    pub fn to_byte_offset(self, s: &str, units: usize) -> Option<usize> {
+        let mut current = 0;
+        for (i, ch) in s.char_indices() {
+            if current >= units {
+                return Some(i);
+            }
+            current += self.char_len(ch);
+        }
+        (current >= units).then_some(s.len())
+    }
+
+    fn char_len(self, ch: char) -> usize {
+        match self {
+            Self::Utf8 => ch.len_utf8(),
+            Self::Utf16 => ch.len_utf16(),
+            Self::Utf32 => 1,
+        }
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for PositionEncoding {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        match value.to_unquoted_string_str()?.as_ref() {
+            "utf-8" => Ok(Self::Utf8),
+            "utf-16" => Ok(Self::Utf16),
+            "utf-32" => Ok(Self::Utf32),
+            _ => Err(value.invalid("unsupported position encoding")),
+        }
+    }
+}
+
+/// Zero-based position whose `character` is counted in the negotiated `PositionEncoding`
+///
+/// Positions shown to (or given by) users count characters instead, see [`Position::decode()`].
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Position {
     pub line: usize,
     pub character: usize,
 }
 
+impl Position {
+    /// Converts a position counting characters into one counting `encoding` units
+    pub fn encode(self, text: &str, encoding: PositionEncoding) -> Self {
+        let Some(line) = text.split('\n').nth(self.line) else {
+            return self;
+        };
+        let character = line
+            .chars()
+            .take(self.character)
+            .map(|ch| encoding.char_len(ch))
+            .sum::<usize>();
+        let rest = self.character.saturating_sub(line.chars().count());
+        Self {
+            line: self.line,
+            character: character + rest,
+        }
+    }
+
+    /// Converts a position counting `encoding` units into one counting characters
+    ///
+    /// Positions are decoded right before being displayed, because users count characters rather
+    /// than encoding units. A position in the middle of a character is rounded up to the next
+    /// character boundary, and a position beyond `text` (e.g. when the file could not be read and
+    /// `text` is empty) is returned as is.
+    pub fn decode(self, text: &str, encoding: PositionEncoding) -> Self {
+        let Some(line) = text.split('\n').nth(self.line) else {
+            return self;
+        };
+        let mut units = 0;
+        let mut character = 0;
+        for ch in line.chars() {
+            if units >= self.character {
+                break;
+            }
+            units += encoding.char_len(ch);
+            character += 1;
+        }
+        Self {
+            line: self.line,
+            character: character + self.character.saturating_sub(units),
+        }
+    }
+}
+
 impl std::ops::Sub for Position {
     type Output = Self;
 
@@ -245,10 +353,28 @@ impl PositionRange {
         Some(line)
     }
 
-    fn find_byte_offset(text: &str, target: Position) -> Option<usize> {
+    /// Converts a range counting characters into one counting `encoding` units
+    pub fn encode(self, text: &str, encoding: PositionEncoding) -> Self {
+        Self {
+            start: self.start.encode(text, encoding),
+            end: self.end.encode(text, encoding),
+        }
+    }
+
+    /// Converts a range counting `encoding` units into one counting characters
+    pub fn decode(self, text: &str, encoding: PositionEncoding) -> Self {
+        Self {
+            start: self.start.decode(text, encoding),
+            end: self.end.decode(text, encoding),
+        }
+    }
+
+    fn find_byte_offset(text: &str, target: Position, encoding: PositionEncoding) -> Option<usize> {
         let mut current = Position::default();
         for (i, ch) in text.char_indices() {
-            if current == target || (ch == '\n' && current.line == target.line) {
+            // `>=` rounds a position in the middle of a character up to the next boundary
+            if current.line == target.line && (current.character >= target.character || ch == '\n')
+            {
                 return Some(i);
             }
 
@@ -256,21 +382,25 @@ impl PositionRange {
                 current.line += 1;
                 current.character = 0;
             } else {
-                current.character += 1;
+                current.character += encoding.char_len(ch);
             }
         }
 
         Some(text.len())
     }
 
-    fn get_byte_positions(self, text: &str) -> Option<(usize, usize)> {
-        let start = Self::find_byte_offset(text, self.start)?;
-        let end = Self::find_byte_offset(&text[start..], self.end - self.start)? + start;
-        Some((start, end))
+    pub fn get_byte_positions(
+        self,
+        text: &str,
+        encoding: PositionEncoding,
+    ) -> Option<(usize, usize)> {
+        let start = Self::find_byte_offset(text, self.start, encoding)?;
+        let end = Self::find_byte_offset(text, self.end, encoding)?;
+        Some((start, end.max(start)))
     }
 
-    pub fn get_range_text(self, text: &str) -> Option<&str> {
-        let (start, end) = self.get_byte_positions(text)?;
+    pub fn get_range_text(self, text: &str, encoding: PositionEncoding) -> Option<&str> {
+        let (start, end) = self.get_byte_positions(text, encoding)?;
         text.get(start..end)
     }
 }
@@ -299,3 +429,79 @@ impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for PositionRange {
         })
     }
 }
+
+/// Text of a `string | MarkupContent` value (e.g., documentation or tooltips)
+#[derive(Debug, Clone)]
+pub struct MarkupText(pub String);
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for MarkupText {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        if value.kind().is_string() {
+            return Ok(Self(value.try_into()?));
+        }
+        Ok(Self(JsonObject::new(value)?.convert_required("value")?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: &str = "a😀b";
+
+    fn pos(line: usize, character: usize) -> Position {
+        Position { line, character }
+    }
+
+    fn range(start: Position, end: Position) -> PositionRange {
+        PositionRange { start, end }
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let cases = [
+            (PositionEncoding::Utf8, [0, 1, 5, 6]),
+            (PositionEncoding::Utf16, [0, 1, 3, 4]),
+            (PositionEncoding::Utf32, [0, 1, 2, 3]),
+        ];
+        for (encoding, units) in cases {
+            for (character, units) in units.into_iter().enumerate() {
+                let encoded = pos(0, character).encode(LINE, encoding);
+                assert_eq!(encoded, pos(0, units), "{}", encoding.name());
+                assert_eq!(encoded.decode(LINE, encoding), pos(0, character));
+            }
+        }
+    }
+
+    #[test]
+    fn mid_character_rounds_up() {
+        // Inside the surrogate pair (UTF-16) or the 4-byte sequence (UTF-8) of the emoji
+        assert_eq!(pos(0, 2).decode(LINE, PositionEncoding::Utf16), pos(0, 2));
+        assert_eq!(pos(0, 3).decode(LINE, PositionEncoding::Utf8), pos(0, 2));
+
+        assert_eq!(PositionEncoding::Utf16.to_byte_offset(LINE, 2), Some(5));
+        assert_eq!(PositionEncoding::Utf8.to_byte_offset(LINE, 2), Some(5));
+
+        let r = range(pos(0, 0), pos(0, 2));
+        assert_eq!(r.get_range_text(LINE, PositionEncoding::Utf16), Some("a😀"));
+    }
+
+    #[test]
+    fn past_end_of_line_is_clamped() {
+        let text = "a😀b\nxy";
+        let r = range(pos(0, 3), pos(0, 100));
+        assert_eq!(
+            r.get_byte_positions(text, PositionEncoding::Utf16),
+            Some((5, 6))
+        );
+        assert_eq!(r.get_range_text(text, PositionEncoding::Utf16), Some("b"));
+
+        let r = range(pos(1, 1), pos(5, 0));
+        assert_eq!(r.get_range_text(text, PositionEncoding::Utf8), Some("y"));
+
+        assert_eq!(PositionEncoding::Utf16.to_byte_offset(LINE, 4), Some(6));
+        assert_eq!(PositionEncoding::Utf16.to_byte_offset(LINE, 5), None);
+    }
+}
@@ -3,19 +3,29 @@ use std::{
     io::{BufRead, BufReader, Write},
     path::{Path, PathBuf},
     process::{Child, ChildStdin, ChildStdout, Command, Stdio},
-    sync::mpsc::{Receiver, Sender},
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender},
 };
 
 use nojson::RawJsonOwned;
 use orfail::OrFail;
 
 use crate::{
+    diagnostic::{self, DiagnosticStore},
+    file_watcher::{FileWatcher, WatcherRegistry},
     json::JsonObject,
     lsp::{self, DocumentUri},
+    open_documents::{self, OpenDocuments},
+    progress::{self, ProgressTracker},
 };
 
 const INITIALIZE_REQUEST_ID: u32 = 0;
 
+/// Proxy-specific request that returns the `ServerCapabilities` from the `initialize` response
+///
+/// Params: none
+/// Result: `ServerCapabilities`
+pub const GET_SERVER_CAPABILITIES_METHOD: &str = "lspterm/serverCapabilities";
+
 #[derive(Debug)]
 pub enum LspMessage {
     Request {
@@ -35,6 +45,10 @@ pub enum LspMessage {
         request_id: RawJsonOwned,
         result: Result<RawJsonOwned, RawJsonOwned>,
     },
+    NotificationFromLspServer {
+        method: String,
+        params: Option<RawJsonOwned>,
+    },
     LspServerStdoutError,
 }
 
@@ -87,26 +101,43 @@ impl LspServer {
         let mut stdout = BufReader::new(process.stdout.take().or_fail()?);
 
         // Initialize the LSP server
-        initialize_lsp_server(&spec, workspace_folder_uri, &mut stdout, &mut stdin).or_fail()?;
+        let server_capabilities =
+            initialize_lsp_server(&spec, &workspace_folder_uri, &mut stdout, &mut stdin)
+                .or_fail()?;
 
         let (message_tx, message_rx) = std::sync::mpsc::channel();
         let message_tx_for_stdout = message_tx.clone();
+        let watcher_registry = WatcherRegistry::default();
 
         // Spawn thread to handle stdin (sending messages to LSP server)
         std::thread::spawn(move || {
-            if let Err(e) = Self::run_stdin_loop(stdin, message_rx) {
+            if let Err(e) = Self::run_stdin_loop(stdin, message_rx, server_capabilities) {
                 eprintln!("[ERROR] LSP server stdin thread error: {e}");
             }
         });
 
         // Spawn thread to handle stdout (receiving messages from LSP server)
+        let watcher_registry_for_stdout = watcher_registry.clone();
         std::thread::spawn(move || {
-            if let Err(e) = Self::run_stdout_loop(stdout, message_tx_for_stdout.clone()) {
+            if let Err(e) = Self::run_stdout_loop(
+                stdout,
+                message_tx_for_stdout.clone(),
+                watcher_registry_for_stdout,
+            ) {
                 eprintln!("[ERROR] LSP server stdout thread error: {e}");
                 let _ = message_tx_for_stdout.send(LspMessage::LspServerStdoutError);
             }
         });
 
+        // Spawn thread to poll the workspace for `workspace/didChangeWatchedFiles`
+        let file_watcher =
+            FileWatcher::new(&workspace_folder_uri, watcher_registry, message_tx.clone());
+        std::thread::spawn(move || {
+            if let Err(e) = file_watcher.run() {
+                eprintln!("[ERROR] file watcher thread error: {e}");
+            }
+        });
+
         Ok(Self {
             process,
             message_tx,
@@ -158,36 +189,164 @@ impl LspServer {
     fn run_stdin_loop(
         mut stdin: ChildStdin,
         message_rx: Receiver<LspMessage>,
+        server_capabilities: RawJsonOwned,
     ) -> orfail::Result<()> {
         let mut ongoing_requests = HashMap::new();
         let mut next_request_id = INITIALIZE_REQUEST_ID + 1;
+        let mut open_documents = OpenDocuments::default();
+        let mut diagnostics = DiagnosticStore::default();
+        let mut progress = ProgressTracker::default();
+
+        loop {
+            progress.notify_if_idle();
+            let msg = if let Some(timeout) = progress.next_timeout() {
+                match message_rx.recv_timeout(timeout) {
+                    Ok(msg) => msg,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            } else {
+                let Ok(msg) = message_rx.recv() else {
+                    break;
+                };
+                msg
+            };
 
-        while let Ok(msg) = message_rx.recv() {
             match msg {
                 LspMessage::Request {
                     method,
                     params,
                     reply_tx,
                 } => {
-                    let json = lsp::send_request(&mut stdin, next_request_id, &method, params)
+                    for json in open_documents.sync_with_disk(&mut stdin) {
+                        println!("--> {json}");
+                    }
+
+                    if method == progress::WAIT_FOR_IDLE_METHOD {
+                        // Proxy-specific request that is replied to once the LSP server becomes idle
+                        progress.add_waiter(reply_tx);
+                        continue;
+                    }
+                    if method == GET_SERVER_CAPABILITIES_METHOD {
+                        // Proxy-specific request that is not forwarded to the LSP server
+                        let _ = reply_tx.send(Ok(server_capabilities.clone()));
+                        continue;
+                    }
+                    if method == diagnostic::GET_DIAGNOSTICS_METHOD {
+                        // Proxy-specific request that is not forwarded to the LSP server
+                        let uris = match params.as_ref().map(diagnostic::get_uris).transpose() {
+                            Ok(uris) => uris.flatten(),
+                            Err(e) => {
+                                eprintln!("[WARN] invalid {method} params: {e}");
+                                None
+                            }
+                        };
+                        for uri in uris.iter().flatten() {
+                            // Opening a document makes the LSP server publish its diagnostics
+                            match open_documents.ensure_open(&mut stdin, uri) {
+                                Ok(Some(json)) => println!("--> {json}"),
+                                Ok(None) => {}
+                                Err(e) => eprintln!(
+                                    "[WARN] failed to open document '{}': {e}",
+                                    uri.path().display()
+                                ),
+                            }
+                        }
+                        let _ = reply_tx.send(Ok(diagnostics.get_diagnostics(uris.as_deref())));
+                        continue;
+                    }
+
+                    let uri = params
+                        .as_ref()
+                        .and_then(open_documents::get_text_document_uri);
+                    if let Some(uri) = &uri {
+                        match open_documents.ensure_open(&mut stdin, uri) {
+                            Ok(Some(json)) => println!("--> {json}"),
+                            Ok(None) => {}
+                            Err(e) => eprintln!(
+                                "[WARN] failed to open document '{}': {e}",
+                                uri.path().display()
+                            ),
+                        }
+                    }
+
+                    let (lsp_method, params) = if method == diagnostic::PULL_DIAGNOSTICS_METHOD {
+                        // Proxy-specific request that is forwarded with the previous result IDs
+                        let (lsp_method, params) = diagnostics.build_pull_request(uri.as_ref());
+                        (lsp_method, Some(params))
+                    } else {
+                        (method.as_str(), params)
+                    };
+                    let json = lsp::send_request(&mut stdin, next_request_id, lsp_method, params)
                         .or_fail()?;
                     println!("--> {json}");
-                    ongoing_requests.insert(next_request_id, reply_tx);
+                    ongoing_requests.insert(next_request_id, (method, uri, reply_tx));
                     next_request_id += 1;
                 }
                 LspMessage::Notification { method, params } => {
+                    let uri = params
+                        .as_ref()
+                        .and_then(open_documents::get_text_document_uri);
+                    match (method.as_str(), uri) {
+                        ("textDocument/didOpen", Some(uri)) if open_documents.is_open(&uri) => {
+                            eprintln!(
+                                "[WARN] ignored didOpen for already opened document '{}'",
+                                uri.path().display()
+                            );
+                            continue;
+                        }
+                        ("textDocument/didOpen", Some(uri)) => {
+                            open_documents.handle_did_open(uri);
+                        }
+                        ("textDocument/didClose", Some(uri)) => {
+                            open_documents.handle_did_close(&uri);
+                        }
+                        _ => {}
+                    }
+
                     let json = lsp::send_notification(&mut stdin, &method, params).or_fail()?;
                     println!("--> {json}");
                 }
                 LspMessage::ResponseFromLspServer { request_id, result } => {
-                    if let Some(reply_tx) = ongoing_requests.remove(&request_id) {
-                        let _ = reply_tx.send(result);
+                    let Some((method, uri, reply_tx)) = ongoing_requests.remove(&request_id) else {
+                        continue;
+                    };
+
+                    // Keep pulled diagnostics so that they can be merged with the pushed ones
+                    if let Ok(report) = &result
+                        && method == diagnostic::PULL_DIAGNOSTICS_METHOD
+                    {
+                        let handled = match uri {
+                            Some(uri) => {
+                                diagnostics.handle_document_diagnostic_report(uri, report.value())
+                            }
+                            None => diagnostics.handle_workspace_diagnostic_report(report.value()),
+                        };
+                        if let Err(e) = handled {
+                            eprintln!("[WARN] invalid {method} result: {e}");
+                        }
                     }
+                    let _ = reply_tx.send(result);
                 }
                 LspMessage::ResponseToLspServer { request_id, result } => {
                     let json = lsp::send_response(&mut stdin, request_id, result).or_fail()?;
                     println!("--> {json}");
                 }
+                LspMessage::NotificationFromLspServer { method, params } => {
+                    let Some(params) = params else {
+                        continue;
+                    };
+                    let handled = match method.as_str() {
+                        "textDocument/publishDiagnostics" => {
+                            diagnostics.handle_publish_diagnostics(params.value())
+                        }
+                        "$/progress" => progress.handle_progress(params.value()),
+                        _ => Ok(()),
+                    };
+                    if let Err(e) = handled {
+                        eprintln!("[WARN] invalid {method} params: {e}");
+                    }
+                }
                 LspMessage::LspServerStdoutError => break,
             }
         }
@@ -197,12 +356,23 @@ impl LspServer {
     fn run_stdout_loop(
         mut stdout: BufReader<ChildStdout>,
         message_tx: Sender<LspMessage>,
+        watcher_registry: WatcherRegistry,
     ) -> orfail::Result<()> {
         while let Some(json) = lsp::recv_message(&mut stdout).or_fail()? {
             println!("<-- {json}");
 
             let object = JsonObject::new(json.value()).or_fail()?;
             let Some(request_id) = object.get_optional("id") else {
+                let method = object.convert_required("method").or_fail()?;
+                let params = object
+                    .get_optional("params")
+                    .map(|params| params.extract().into_owned());
+                if message_tx
+                    .send(LspMessage::NotificationFromLspServer { method, params })
+                    .is_err()
+                {
+                    break;
+                }
                 continue;
             };
 
@@ -214,6 +384,19 @@ impl LspServer {
                         let result = Ok(RawJsonOwned::parse("null").expect("bug"));
                         LspMessage::ResponseToLspServer { request_id, result }
                     }
+                    "client/registerCapability" | "client/unregisterCapability" => {
+                        let params = object.get_required("params").or_fail()?;
+                        let registered = if method == "client/registerCapability" {
+                            watcher_registry.register(params)
+                        } else {
+                            watcher_registry.unregister(params)
+                        };
+                        if let Err(e) = registered {
+                            eprintln!("[WARN] failed to handle {method}: {e}");
+                        }
+                        let result = Ok(RawJsonOwned::parse("null").expect("bug"));
+                        LspMessage::ResponseToLspServer { request_id, result }
+                    }
                     _ => {
                         let result = Err(RawJsonOwned::parse(
                             r#"{"code":-32601, "message":"method not found"}"#,
@@ -243,20 +426,17 @@ impl LspServer {
 
 fn initialize_lsp_server<R, W>(
     spec: &LspServerSpec,
-    workspace_folder_uri: DocumentUri,
+    workspace_folder_uri: &DocumentUri,
     mut reader: R,
     mut writer: W,
-) -> orfail::Result<()>
+) -> orfail::Result<RawJsonOwned>
 where
     R: BufRead,
     W: Write,
 {
     let params = nojson::object(|f| {
         f.member("clientInfo", client_info())?;
-        f.member(
-            "workspaceFolders",
-            [workspace_folder(&workspace_folder_uri)],
-        )?;
+        f.member("workspaceFolders", [workspace_folder(workspace_folder_uri)])?;
         f.member(
             "capabilities",
             nojson::RawJson::parse(include_str!("capabilities.json")).expect("bug"),
@@ -270,13 +450,41 @@ where
         lsp::send_request(&mut writer, INITIALIZE_REQUEST_ID, "initialize", params).or_fail()?;
     println!("--> {json}");
 
-    let json = lsp::recv_message(&mut reader).or_fail()?.or_fail()?;
-    println!("<-- {json}");
+    // Servers may send notifications (e.g., `window/logMessage`) before the response
+    let response = loop {
+        let json = lsp::recv_message(&mut reader)
+            .or_fail()?
+            .or_fail_with(|()| "LSP server exited before responding to initialize".to_owned())?;
+        println!("<-- {json}");
+        let message = JsonObject::new(json.value()).or_fail()?;
+        let is_response = message.get_optional("method").is_none()
+            && message.convert_optional::<u32>("id").ok().flatten() == Some(INITIALIZE_REQUEST_ID);
+        if is_response {
+            break json;
+        }
+    };
+    let response = JsonObject::new(response.value()).or_fail()?;
+    if let Some(error) = response.get_optional("error") {
+        return Err(orfail::Failure::new(format!(
+            "LSP server failed to initialize: {error}"
+        )));
+    }
+    let server_capabilities = response
+        .get_required("result")
+        .and_then(JsonObject::new)
+        .and_then(|result| result.get_required("capabilities"))
+        .or_fail()?
+        .extract()
+        .into_owned();
+
+    // Subcommands convert positions according to the negotiated encoding, so reject unknown ones early
+    lsp::PositionEncoding::from_capabilities(server_capabilities.value())
+        .or_fail_with(|e| format!("LSP server selected an unsupported position encoding: {e}"))?;
 
     let json = lsp::send_notification(&mut writer, "initialized", ()).or_fail()?;
     println!("--> {json}");
 
-    Ok(())
+    Ok(server_capabilities)
 }
 
 fn client_info() -> impl nojson::DisplayJson {
@@ -16,10 +16,94 @@ fn main() -> noargs::Result<()> {
         // textDocument/definition
         return Ok(());
     };
+    let Some(args) = lspterm::subcommand_implementation::try_run(args)? else {
+        // textDocument/implementation
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_type_definition::try_run(args)? else {
+        // textDocument/typeDefinition
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_declaration::try_run(args)? else {
+        // textDocument/declaration
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_references::try_run(args)? else {
+        // textDocument/references
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_calls::try_run(args)? else {
+        // textDocument/prepareCallHierarchy, callHierarchy/incomingCalls, callHierarchy/outgoingCalls
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_types::try_run(args)? else {
+        // textDocument/prepareTypeHierarchy, typeHierarchy/supertypes, typeHierarchy/subtypes
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_highlight::try_run(args)? else {
+        // textDocument/documentHighlight
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_symbols::try_run(args)? else {
+        // workspace/symbol
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_outline::try_run(args)? else {
+        // textDocument/documentSymbol
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_folding::try_run(args)? else {
+        // textDocument/foldingRange
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_selection_range::try_run(args)? else {
+        // textDocument/selectionRange
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_links::try_run(args)? else {
+        // textDocument/documentLink, documentLink/resolve
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_inlay_hints::try_run(args)? else {
+        // textDocument/inlayHint, inlayHint/resolve
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_code_lens::try_run(args)? else {
+        // textDocument/codeLens, codeLens/resolve, workspace/executeCommand
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_semantic_tokens::try_run(args)? else {
+        // textDocument/semanticTokens/full, textDocument/semanticTokens/range
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_diagnostics::try_run(args)? else {
+        // textDocument/publishDiagnostics, textDocument/diagnostic, workspace/diagnostic
+        return Ok(());
+    };
     let Some(args) = lspterm::subcommand_rename::try_run(args)? else {
         // textDocument/rename
         return Ok(());
     };
+    let Some(args) = lspterm::subcommand_format::try_run(args)? else {
+        // textDocument/formatting, textDocument/rangeFormatting
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_signature::try_run(args)? else {
+        // textDocument/signatureHelp
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_mv::try_run(args)? else {
+        // workspace/willRenameFiles, workspace/didRenameFiles
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_touch::try_run(args)? else {
+        // workspace/willCreateFiles, workspace/didCreateFiles
+        return Ok(());
+    };
+    let Some(args) = lspterm::subcommand_rm::try_run(args)? else {
+        // workspace/willDeleteFiles, workspace/didDeleteFiles
+        return Ok(());
+    };
     let Some(args) = lspterm::subcommand_completion::try_run(args)? else {
         // textDocument/completion
         return Ok(());
@@ -33,13 +117,6 @@ fn main() -> noargs::Result<()> {
         return Ok(());
     };
 
-    // MEMO:
-    // - textDocument/references
-    // - workspace/symbol
-    // - textDocument/implementation
-    // - textDocument/typeDefinition
-    // - textDocument/declaration
-
     if let Some(help) = args.finish()? {
         print!("{help}");
     }
@@ -0,0 +1,227 @@
+use std::{collections::HashMap, io::Write, path::Path, time::SystemTime};
+
+use orfail::OrFail;
+
+use crate::lsp::{self, DocumentUri};
+
+/// Documents that have been opened on the LSP server via `textDocument/didOpen`
+#[derive(Debug, Default)]
+pub struct OpenDocuments {
+    documents: HashMap<DocumentUri, OpenDocument>,
+}
+
+#[derive(Debug)]
+struct OpenDocument {
+    version: i64,
+
+    /// `None` if the document was opened by a proxy client, which is then responsible for keeping it in sync
+    disk_state: Option<DiskState>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct DiskState {
+    mtime: SystemTime,
+    len: u64,
+    text: String,
+}
+
+impl DiskState {
+    fn read(uri: &DocumentUri) -> orfail::Result<Self> {
+        let metadata = std::fs::metadata(uri.path()).or_fail_with(|e| {
+            format!("failed to get metadata of '{}': {e}", uri.path().display())
+        })?;
+        Ok(Self {
+            mtime: metadata.modified().or_fail()?,
+            len: metadata.len(),
+            text: uri.read_to_string().or_fail()?,
+        })
+    }
+}
+
+impl OpenDocument {
+    /// Sends `textDocument/didChange` if the on-disk text has changed since the last sync
+    fn sync_with_disk<W: Write>(
+        &mut self,
+        writer: W,
+        uri: &DocumentUri,
+    ) -> orfail::Result<Option<String>> {
+        let Some(old_state) = &self.disk_state else {
+            return Ok(None);
+        };
+
+        let metadata = std::fs::metadata(uri.path()).or_fail()?;
+        if metadata.modified().ok() == Some(old_state.mtime) && metadata.len() == old_state.len {
+            return Ok(None);
+        }
+
+        let new_state = DiskState::read(uri).or_fail()?;
+        let mut json = None;
+        if new_state.text != old_state.text {
+            let version = self.version + 1;
+            let params = nojson::object(|f| {
+                f.member(
+                    "textDocument",
+                    nojson::object(|f| {
+                        f.member("uri", uri)?;
+                        f.member("version", version)
+                    }),
+                )?;
+                f.member(
+                    "contentChanges",
+                    [nojson::object(|f| f.member("text", &new_state.text))],
+                )
+            });
+            json =
+                Some(lsp::send_notification(writer, "textDocument/didChange", params).or_fail()?);
+            self.version = version;
+        }
+        self.disk_state = Some(new_state);
+        Ok(json)
+    }
+}
+
+impl OpenDocuments {
+    pub fn is_open(&self, uri: &DocumentUri) -> bool {
+        self.documents.contains_key(uri)
+    }
+
+    /// Sends `textDocument/didOpen` with the on-disk text if `uri` has not been opened yet
+    ///
+    /// Returns the sent JSON message, if any.
+    pub fn ensure_open<W: Write>(
+        &mut self,
+        writer: W,
+        uri: &DocumentUri,
+    ) -> orfail::Result<Option<String>> {
+        if self.is_open(uri) {
+            return Ok(None);
+        }
+
+        let disk_state = DiskState::read(uri).or_fail()?;
+        let version = 0;
+        let params = nojson::object(|f| {
+            f.member(
+                "textDocument",
+                nojson::object(|f| {
+                    f.member("uri", uri)?;
+                    f.member("languageId", language_id(uri.path()))?;
+                    f.member("version", version)?;
+                    f.member("text", &disk_state.text)
+                }),
+            )
+        });
+        let json = lsp::send_notification(writer, "textDocument/didOpen", params).or_fail()?;
+        self.documents.insert(
+            uri.clone(),
+            OpenDocument {
+                version,
+                disk_state: Some(disk_state),
+            },
+        );
+        Ok(Some(json))
+    }
+
+    /// Brings the documents opened by the proxy up to date with their on-disk contents
+    ///
+    /// Sends `textDocument/didChange` for modified files and `textDocument/didClose` for deleted ones,
+    /// returning the sent JSON messages. Failures are logged per document and do not stop the sync.
+    pub fn sync_with_disk<W: Write>(&mut self, mut writer: W) -> Vec<String> {
+        let mut sent = Vec::new();
+        let mut closed = Vec::new();
+        for (uri, document) in &mut self.documents {
+            if document.disk_state.is_none() {
+                continue;
+            }
+
+            if !uri.path().exists() {
+                // Forget the document even if the notification fails, so it is not closed twice
+                closed.push(uri.clone());
+                let params = nojson::object(|f| {
+                    f.member("textDocument", nojson::object(|f| f.member("uri", uri)))
+                });
+                match lsp::send_notification(&mut writer, "textDocument/didClose", params) {
+                    Ok(json) => sent.push(json),
+                    Err(e) => eprintln!(
+                        "[WARN] failed to close document '{}': {e}",
+                        uri.path().display()
+                    ),
+                }
+                continue;
+            }
+
+            match document.sync_with_disk(&mut writer, uri) {
+                Ok(json) => sent.extend(json),
+                Err(e) => eprintln!(
+                    "[WARN] failed to sync document '{}': {e}",
+                    uri.path().display()
+                ),
+            }
+        }
+
+        for uri in closed {
+            self.documents.remove(&uri);
+        }
+        sent
+    }
+
+    /// Records a document opened by a proxy client
+    pub fn handle_did_open(&mut self, uri: DocumentUri) {
+        self.documents.insert(
+            uri,
+            OpenDocument {
+                version: 0,
+                disk_state: None,
+            },
+        );
+    }
+
+    /// Forgets a document closed by a proxy client
+    pub fn handle_did_close(&mut self, uri: &DocumentUri) {
+        self.documents.remove(uri);
+    }
+}
+
+/// Extracts `params.textDocument.uri` from a request or notification
+pub fn get_text_document_uri(params: &nojson::RawJsonOwned) -> Option<DocumentUri> {
+    let text_document = params.value().to_member("textDocument").ok()?.get()?;
+    let uri = text_document.to_member("uri").ok()?.get()?;
+    uri.try_into().ok()
+}
+
+fn language_id(path: &Path) -> &'static str {
+    let Some(extension) = path.extension().and_then(|ext| ext.to_str()) else {
+        return "plaintext";
+    };
+    match extension {
+        "rs" => "rust",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => "cpp",
+        "cs" => "csharp",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "scala" => "scala",
+        "swift" => "swift",
+        "zig" => "zig",
+        "py" | "pyi" => "python",
+        "rb" => "ruby",
+        "php" => "php",
+        "lua" => "lua",
+        "hs" => "haskell",
+        "ml" | "mli" => "ocaml",
+        "ex" | "exs" => "elixir",
+        "erl" | "hrl" => "erlang",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "javascriptreact",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "typescriptreact",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "md" => "markdown",
+        "sh" | "bash" => "shellscript",
+        _ => "plaintext",
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    collections::HashSet,
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+
+use orfail::OrFail;
+
+use crate::json::JsonObject;
+
+/// Proxy-specific request that is replied to (with `null`) once the LSP server becomes idle
+///
+/// The server is considered idle when no `$/progress` work (e.g., indexing) has been active for [`IDLE_SETTLE_TIME`]
+/// since both the last progress message and the arrival of the request.
+pub const WAIT_FOR_IDLE_METHOD: &str = "lspterm/waitForIdle";
+
+/// Some servers start reporting progress only after a short delay, so idleness must last this long
+pub const IDLE_SETTLE_TIME: Duration = Duration::from_secs(1);
+
+type ReplySender = Sender<Result<nojson::RawJsonOwned, nojson::RawJsonOwned>>;
+
+/// Tracks `$/progress` work-done progress reported by the LSP server
+#[derive(Debug)]
+pub struct ProgressTracker {
+    active_tokens: HashSet<nojson::RawJsonOwned>,
+    last_activity: Instant,
+    waiters: Vec<Waiter>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    reply_tx: ReplySender,
+    arrival: Instant,
+}
+
+impl Default for ProgressTracker {
+    fn default() -> Self {
+        Self {
+            active_tokens: HashSet::new(),
+            last_activity: Instant::now(),
+            waiters: Vec::new(),
+        }
+    }
+}
+
+impl ProgressTracker {
+    pub fn handle_progress(&mut self, params: nojson::RawJsonValue<'_, '_>) -> orfail::Result<()> {
+        let object = JsonObject::new(params).or_fail()?;
+        let token = object
+            .get_required("token")
+            .or_fail()?
+            .extract()
+            .into_owned();
+        let value = JsonObject::new(object.get_required("value").or_fail()?).or_fail()?;
+        let kind: String = value.convert_required("kind").or_fail()?;
+        match kind.as_str() {
+            "begin" | "report" => {
+                self.active_tokens.insert(token);
+            }
+            "end" => {
+                self.active_tokens.remove(&token);
+            }
+            _ => {}
+        }
+        self.last_activity = Instant::now();
+        Ok(())
+    }
+
+    pub fn add_waiter(&mut self, reply_tx: ReplySender) {
+        self.waiters.push(Waiter {
+            reply_tx,
+            arrival: Instant::now(),
+        });
+    }
+
+    /// Returns the remaining settle time of `waiter` (zero if the server is idle for it)
+    fn remaining_settle_time(&self, waiter: &Waiter) -> Duration {
+        // Files may have just been opened for the waiter, so quietness before its arrival does not count
+        let since = self.last_activity.max(waiter.arrival);
+        IDLE_SETTLE_TIME.saturating_sub(since.elapsed())
+    }
+
+    /// Returns how long to wait before calling [`ProgressTracker::notify_if_idle`] again,
+    /// or `None` if it only needs to be called after the next message
+    pub fn next_timeout(&self) -> Option<Duration> {
+        if !self.active_tokens.is_empty() {
+            return None;
+        }
+        self.waiters
+            .iter()
+            .map(|waiter| self.remaining_settle_time(waiter))
+            .min()
+    }
+
+    /// Replies to the waiters for which the LSP server has been idle long enough
+    pub fn notify_if_idle(&mut self) {
+        if !self.active_tokens.is_empty() {
+            return;
+        }
+        let (ready, waiting) = std::mem::take(&mut self.waiters)
+            .into_iter()
+            .partition::<Vec<_>, _>(|waiter| self.remaining_settle_time(waiter).is_zero());
+        self.waiters = waiting;
+        for waiter in ready {
+            let _ = waiter
+                .reply_tx
+                .send(Ok(nojson::RawJsonOwned::parse("null").expect("bug")));
+        }
+    }
+}
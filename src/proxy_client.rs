@@ -1,8 +1,13 @@
-use std::{io::BufReader, net::TcpStream};
+use std::{io::BufReader, net::TcpStream, time::Duration};
 
 use orfail::OrFail;
 
-use crate::{json::JsonObject, lsp, proxy_server::DEFAULT_PORT};
+use crate::{
+    json::JsonObject,
+    lsp::{self, PositionEncoding},
+    lsp_server::GET_SERVER_CAPABILITIES_METHOD,
+    proxy_server::DEFAULT_PORT,
+};
 
 pub const PORT_OPT: noargs::OptSpec = noargs::opt("port")
     .short('p')
@@ -11,10 +16,22 @@ pub const PORT_OPT: noargs::OptSpec = noargs::opt("port")
     .env("LSPTERM_PORT")
     .doc("LSP proxy server port");
 
+/// Item that may be returned partially and completed by a `*/resolve` request
+pub trait Resolvable:
+    for<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>, Error = nojson::JsonParseError>
+{
+    /// Returns `true` if the properties shown to the user are already filled in
+    fn is_resolved(&self) -> bool;
+
+    /// Original JSON, which is sent back as-is to resolve the item
+    fn json(&self) -> &nojson::RawJsonOwned;
+}
+
 #[derive(Debug)]
 pub struct ProxyClient {
     stream: BufReader<TcpStream>,
     next_request_id: u32,
+    position_encoding: Option<PositionEncoding>,
 }
 
 impl ProxyClient {
@@ -23,9 +40,26 @@ impl ProxyClient {
         Ok(Self {
             stream,
             next_request_id: 0,
+            position_encoding: None,
         })
     }
 
+    /// Returns the position encoding negotiated between the proxy and the LSP server
+    pub fn position_encoding(&mut self) -> orfail::Result<PositionEncoding> {
+        if let Some(encoding) = self.position_encoding {
+            return Ok(encoding);
+        }
+        let capabilities = self.call(GET_SERVER_CAPABILITIES_METHOD, ()).or_fail()?;
+        let encoding = PositionEncoding::from_capabilities(capabilities.value()).or_fail()?;
+        self.position_encoding = Some(encoding);
+        Ok(encoding)
+    }
+
+    /// Sets the maximum time to wait for a response (`None` waits indefinitely)
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> orfail::Result<()> {
+        self.stream.get_ref().set_read_timeout(timeout).or_fail()
+    }
+
     pub fn call<T>(&mut self, method: &str, params: T) -> orfail::Result<nojson::RawJsonOwned>
     where
         T: nojson::DisplayJson,
@@ -50,6 +84,27 @@ impl ProxyClient {
         response.convert_required("result").or_fail()
     }
 
+    /// Resolves the unresolved `items` with `method` (e.g. `codeLens/resolve`)
+    ///
+    /// Resolution stops at the first failed request, leaving the remaining items as they are.
+    pub fn resolve_items<T: Resolvable>(&mut self, method: &str, items: &mut [T]) {
+        for item in items {
+            if item.is_resolved() {
+                continue;
+            }
+            let resolved = self
+                .call(method, item.json())
+                .and_then(|resolved| T::try_from(resolved.value()).or_fail());
+            match resolved {
+                Ok(resolved) => *item = resolved,
+                Err(e) => {
+                    eprintln!("[WARN] {method} failed: {e}");
+                    break;
+                }
+            }
+        }
+    }
+
     pub fn cast<T>(&mut self, method: &str, params: T) -> orfail::Result<()>
     where
         T: nojson::DisplayJson,
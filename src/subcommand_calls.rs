@@ -0,0 +1,143 @@
+use orfail::OrFail;
+
+use crate::{
+    args::RAW_FLAG,
+    json::JsonObject,
+    location::Location,
+    lsp::PositionRange,
+    proxy_client::{PORT_OPT, ProxyClient},
+    symbol::{HierarchyChild, HierarchyDirection, HierarchyItem, HierarchyTreePrinter},
+    target::{TARGET_ARG, TargetLocation},
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("calls")
+        .doc("Show the call hierarchy of a function (callHierarchy/incomingCalls, callHierarchy/outgoingCalls)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let incoming = noargs::flag("incoming")
+        .short('i')
+        .doc("Show the callers of the target (default)")
+        .take(&mut args)
+        .is_present();
+    let outgoing = noargs::flag("outgoing")
+        .short('o')
+        .doc("Show the callees of the target")
+        .take(&mut args)
+        .is_present();
+    let depth: usize = noargs::opt("depth")
+        .short('d')
+        .ty("INTEGER")
+        .default("1")
+        .doc("Maximum depth of the call tree")
+        .take(&mut args)
+        .then(|a| a.value().parse())?;
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let target: TargetLocation = TARGET_ARG.take(&mut args).then(|a| a.value().parse())?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    (!(incoming && outgoing))
+        .or_fail_with(|()| "--incoming and --outgoing cannot be specified together".to_owned())?;
+    target.file.check_existence().or_fail()?;
+
+    let direction = if outgoing {
+        Direction::Outgoing
+    } else {
+        Direction::Incoming
+    };
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    let text = target.file.read_to_string().or_fail()?;
+    let params = nojson::object(|f| target.fmt_json_object(f, &text, encoding));
+    let result = client
+        .call("textDocument/prepareCallHierarchy", params)
+        .or_fail()?;
+    if raw {
+        println!("{result}");
+    }
+
+    let items = Option::<Vec<HierarchyItem>>::try_from(result.value())
+        .or_fail()?
+        .unwrap_or_default();
+    HierarchyTreePrinter::new(client, direction, depth, raw, encoding)
+        .or_fail()?
+        .print(&items)
+        .or_fail()?;
+
+    Ok(None)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Incoming,
+    Outgoing,
+}
+
+impl Direction {
+    fn item_member(self) -> &'static str {
+        match self {
+            Self::Incoming => "from",
+            Self::Outgoing => "to",
+        }
+    }
+}
+
+impl HierarchyDirection for Direction {
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Incoming => "Incoming calls",
+            Self::Outgoing => "Outgoing calls",
+        }
+    }
+
+    fn method(&self) -> &'static str {
+        match self {
+            Self::Incoming => "callHierarchy/incomingCalls",
+            Self::Outgoing => "callHierarchy/outgoingCalls",
+        }
+    }
+
+    fn parse_children(
+        &self,
+        parent: &HierarchyItem,
+        result: nojson::RawJsonValue<'_, '_>,
+    ) -> Result<Vec<HierarchyChild>, nojson::JsonParseError> {
+        if result.kind().is_null() {
+            return Ok(Vec::new());
+        }
+
+        // `CallHierarchyIncomingCall[]` or `CallHierarchyOutgoingCall[]`
+        let mut children = Vec::new();
+        for value in result.to_array()? {
+            let object = JsonObject::new(value)?;
+            let item: HierarchyItem = object.convert_required(self.item_member())?;
+            let from_ranges: Vec<PositionRange> =
+                object.convert_optional_or_default("fromRanges")?;
+
+            // Call sites are located in the caller's file
+            let call_site_uri = match self {
+                Self::Incoming => &item.uri,
+                Self::Outgoing => &parent.uri,
+            };
+            let call_sites = from_ranges
+                .into_iter()
+                .map(|range| Location {
+                    uri: call_site_uri.clone(),
+                    range,
+                })
+                .collect();
+            children.push(HierarchyChild { item, call_sites });
+        }
+        Ok(children)
+    }
+}
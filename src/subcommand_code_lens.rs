@@ -0,0 +1,138 @@
+use orfail::OrFail;
+
+use crate::{
+    args::RAW_FLAG,
+    command::Command,
+    json::JsonObject,
+    lsp::{DocumentUri, PositionRange},
+    proxy_client::{PORT_OPT, ProxyClient, Resolvable},
+    target::FILE_ARG,
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("code-lens")
+        .doc("List the code lenses of a file (textDocument/codeLens)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let execute_index: Option<usize> = noargs::opt("execute")
+        .short('e')
+        .ty("INDEX")
+        .doc("Execute the command of the code lens at the specified index (1-based)")
+        .take(&mut args)
+        .present_and_then(|a| a.value().parse::<std::num::NonZeroUsize>())?
+        .map(|i| i.get() - 1);
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let file: DocumentUri = FILE_ARG
+        .take(&mut args)
+        .then(|a| DocumentUri::new(a.value()))?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    file.check_existence().or_fail()?;
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+
+    let params =
+        nojson::object(|f| f.member("textDocument", nojson::object(|f| f.member("uri", &file))));
+    let result = client.call("textDocument/codeLens", params).or_fail()?;
+
+    if raw && execute_index.is_none() {
+        println!("{result}");
+        return Ok(None);
+    }
+
+    let mut lenses = Option::<Vec<CodeLens>>::try_from(result.value())
+        .or_fail()?
+        .unwrap_or_default();
+    if lenses.is_empty() {
+        println!("Not found");
+        return Ok(None);
+    }
+
+    client.resolve_items("codeLens/resolve", &mut lenses);
+
+    if let Some(index) = execute_index {
+        let lens = lenses.get(index).or_fail_with(|()| {
+            format!(
+                "invalid code lens index: {} (available: 1-{})",
+                index + 1,
+                lenses.len()
+            )
+        })?;
+        let command = lens
+            .command
+            .as_ref()
+            .or_fail_with(|()| format!("code lens {} has no command", index + 1))?;
+        let result = command.execute(&mut client).or_fail()?;
+        if raw || !result.value().kind().is_null() {
+            println!("{result}");
+        }
+        eprintln!("=> Executed `{}` ({})", command.title, command.command);
+        return Ok(None);
+    }
+
+    let encoding = client.position_encoding().or_fail()?;
+    let text = file.read_to_string().or_fail()?;
+    let base_dir = std::env::current_dir().or_fail()?;
+    let path = file.relative_path(&base_dir);
+    for (i, lens) in lenses.iter().enumerate() {
+        let start = lens.range.start.decode(&text, encoding);
+        let position = format!(
+            "{}:{}:{}",
+            path.display(),
+            start.line + 1,
+            start.character + 1
+        );
+        match &lens.command {
+            Some(command) => println!(
+                "{}. {position}: `{}` ({})",
+                i + 1,
+                command.title,
+                command.command
+            ),
+            None => println!("{}. {position}: (unresolved)", i + 1),
+        }
+        if let Some(line) = lens.range.get_start_line(&text) {
+            println!("   > {}", line.trim());
+        }
+    }
+
+    Ok(None)
+}
+
+#[derive(Debug, Clone)]
+struct CodeLens {
+    range: PositionRange,
+    command: Option<Command>,
+    json: nojson::RawJsonOwned,
+}
+
+impl Resolvable for CodeLens {
+    fn is_resolved(&self) -> bool {
+        self.command.is_some()
+    }
+
+    fn json(&self) -> &nojson::RawJsonOwned {
+        &self.json
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for CodeLens {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            range: object.convert_required("range")?,
+            command: object.convert_optional("command")?,
+            json: value.extract().into_owned(),
+        })
+    }
+}
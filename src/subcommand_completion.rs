@@ -28,11 +28,13 @@ pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawAr
     target.file.check_existence().or_fail()?;
 
     let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
 
+    let text = target.file.read_to_string().or_fail()?;
     let params = nojson::object(|f| {
         //f.member("context", nojson::object(|f| f.member("triggerKind", 3)))?;
         f.member("context", nojson::object(|f| f.member("triggerKind", 1)))?;
-        target.fmt_json_object(f)
+        target.fmt_json_object(f, &text, encoding)
     });
     let result = client.call("textDocument/completion", params).or_fail()?;
 
@@ -0,0 +1,11 @@
+use crate::location;
+
+pub fn try_run(args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    location::try_run_subcommand(
+        args,
+        noargs::cmd("declaration")
+            .doc("Get declaration location for a symbol (textDocument/declaration)"),
+        "textDocument/declaration",
+        "Declaration",
+    )
+}
@@ -0,0 +1,381 @@
+use std::{io::Write, num::NonZeroUsize, path::Path, time::Duration};
+
+use orfail::OrFail;
+
+use crate::{
+    args::{CONTEXT_OPT, RAW_FLAG},
+    diagnostic::{self, Diagnostic, DiagnosticSeverity, DocumentDiagnostics},
+    json::JsonObject,
+    location,
+    lsp::{DocumentUri, PositionEncoding, PositionRange},
+    lsp_server::GET_SERVER_CAPABILITIES_METHOD,
+    progress,
+    proxy_client::{PORT_OPT, ProxyClient},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("diagnostics")
+        .doc("Show diagnostics reported by the LSP server (textDocument/publishDiagnostics, textDocument/diagnostic)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let context_lines: NonZeroUsize = CONTEXT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let severity: DiagnosticSeverity = noargs::opt("severity")
+        .short('s')
+        .ty("error|warning|info|hint")
+        .default("hint")
+        .doc("Only show diagnostics at or above this severity")
+        .take(&mut args)
+        .then(|a| a.value().parse())?;
+    let timeout: f64 = noargs::opt("timeout")
+        .short('t')
+        .ty("SECONDS")
+        .default("3")
+        .doc("Maximum time to wait for the LSP server to publish diagnostics of the given files")
+        .take(&mut args)
+        .then(|a| a.value().parse())?;
+    let pull = noargs::flag("pull")
+        .short('P')
+        .doc("Pull diagnostics via textDocument/diagnostic (or workspace/diagnostic if no files are given)")
+        .take(&mut args)
+        .is_present();
+    let fail_on: Option<DiagnosticSeverity> = noargs::opt("fail-on")
+        .short('f')
+        .ty("error|warning|info|hint")
+        .doc("Exit with status 1 if any reported diagnostic is at or above this severity")
+        .take(&mut args)
+        .present_and_then(|a| a.value().parse())?;
+    let format: OutputFormat = noargs::opt("format")
+        .ty("markdown|sarif")
+        .default("markdown")
+        .doc("Output format (SARIF 2.1.0 is suitable for CI code scanning)")
+        .take(&mut args)
+        .then(|a| a.value().parse())?;
+    let wait_idle: Option<f64> = noargs::opt("wait-idle")
+        .short('w')
+        .ty("SECONDS")
+        .doc("Wait (up to SECONDS) until the LSP server finishes its work in progress, such as indexing")
+        .take(&mut args)
+        .present_and_then(|a| a.value().parse())?;
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let mut files = Vec::new();
+    while let Some(file) = noargs::arg("[FILE]...")
+        .example("/path/to/file")
+        .doc("Files to show diagnostics for (all files with diagnostics if omitted)")
+        .take(&mut args)
+        .present_and_then(|a| DocumentUri::new(a.value()))?
+    {
+        files.push(file);
+        if args.metadata().help_mode {
+            break;
+        }
+    }
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    for file in &files {
+        file.check_existence().or_fail()?;
+    }
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+    if pull && files.is_empty() {
+        supports_workspace_diagnostics(&mut client)
+            .or_fail()?
+            .or_fail_with(|()| {
+                "LSP server does not support workspace diagnostics; pass FILEs to pull".to_owned()
+            })?;
+    }
+
+    let params = nojson::object(|f| {
+        if !files.is_empty() {
+            f.member("uris", &files)?;
+        }
+        Ok(())
+    });
+
+    if let Some(wait_idle) = wait_idle {
+        // Open the files first so that their analysis is also waited for
+        client
+            .call(diagnostic::GET_DIAGNOSTICS_METHOD, &params)
+            .or_fail()?;
+        client
+            .set_timeout(Some(Duration::from_secs_f64(wait_idle)))
+            .or_fail()?;
+        client
+            .call(progress::WAIT_FOR_IDLE_METHOD, ())
+            .or_fail_with(|e| format!("LSP server did not become idle: {e}"))?;
+        client.set_timeout(None).or_fail()?;
+    }
+
+    if pull {
+        // Pulled diagnostics are recorded by the proxy and merged with the published ones
+        if files.is_empty() {
+            client
+                .call(
+                    diagnostic::PULL_DIAGNOSTICS_METHOD,
+                    nojson::object(|_| Ok(())),
+                )
+                .or_fail()?;
+        }
+        for file in &files {
+            let params = nojson::object(|f| {
+                f.member("textDocument", nojson::object(|f| f.member("uri", file)))
+            });
+            client
+                .call(diagnostic::PULL_DIAGNOSTICS_METHOD, params)
+                .or_fail()?;
+        }
+    }
+
+    let deadline = std::time::Instant::now() + Duration::from_secs_f64(timeout);
+    let (result, documents) = loop {
+        let result = client
+            .call(diagnostic::GET_DIAGNOSTICS_METHOD, &params)
+            .or_fail()?;
+        let documents = Vec::<DocumentDiagnostics>::try_from(result.value()).or_fail()?;
+
+        // Diagnostics of just opened files are published asynchronously
+        let published_all = files
+            .iter()
+            .all(|file| documents.iter().any(|d| d.uri == *file));
+        if published_all || std::time::Instant::now() >= deadline {
+            break (result, documents);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    let mut reported = Vec::new();
+    for document in documents {
+        let mut diagnostics = document
+            .diagnostics
+            .into_iter()
+            .filter(|d| d.severity() <= severity)
+            .collect::<Vec<_>>();
+        if diagnostics.is_empty() {
+            continue;
+        }
+        diagnostics.sort_by_key(|d| (d.range.start, d.severity()));
+        reported.push((document.uri, diagnostics));
+    }
+    let failed = fail_on.is_some_and(|fail_on| {
+        reported
+            .iter()
+            .flat_map(|(_, diagnostics)| diagnostics)
+            .any(|d| d.severity() <= fail_on)
+    });
+
+    match format {
+        _ if raw => println!("{result}"),
+        OutputFormat::Markdown => {
+            if reported.is_empty() {
+                println!("No diagnostics");
+            }
+            for (uri, diagnostics) in &reported {
+                let text = uri.read_to_string().unwrap_or_default();
+                for diagnostic in diagnostics {
+                    print_diagnostic(uri, &text, diagnostic, context_lines.get(), encoding)
+                        .or_fail()?;
+                }
+            }
+        }
+        OutputFormat::Sarif => print_sarif(&reported, encoding).or_fail()?,
+    }
+
+    if failed {
+        std::io::stdout().flush().or_fail()?;
+        std::process::exit(1);
+    }
+
+    Ok(None)
+}
+
+/// Returns whether `diagnosticProvider.workspaceDiagnostics` is enabled in the server capabilities
+fn supports_workspace_diagnostics(client: &mut ProxyClient) -> orfail::Result<bool> {
+    let capabilities = client.call(GET_SERVER_CAPABILITIES_METHOD, ()).or_fail()?;
+    let Some(provider) = JsonObject::new(capabilities.value())
+        .or_fail()?
+        .get_optional("diagnosticProvider")
+    else {
+        return Ok(false);
+    };
+    JsonObject::new(provider)
+        .and_then(|provider| provider.convert_optional_or_default("workspaceDiagnostics"))
+        .or_fail()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum OutputFormat {
+    Markdown,
+    Sarif,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" => Ok(Self::Markdown),
+            "sarif" => Ok(Self::Sarif),
+            _ => Err(format!(
+                "unknown format '{s}': expected one of markdown, sarif"
+            )),
+        }
+    }
+}
+
+fn print_diagnostic(
+    uri: &DocumentUri,
+    text: &str,
+    diagnostic: &Diagnostic,
+    context_lines: usize,
+    encoding: PositionEncoding,
+) -> orfail::Result<()> {
+    let mut message_lines = diagnostic.message.lines();
+
+    print!("## {}", diagnostic.severity().name());
+    if let Some(code) = &diagnostic.code {
+        print!("[{code}]");
+    }
+    print!(": {}", message_lines.next().unwrap_or_default());
+    if let Some(source) = &diagnostic.source {
+        print!(" ({source})");
+    }
+    println!();
+    println!();
+
+    location::print_source_excerpt(uri, text, diagnostic.range, context_lines, encoding, false)
+        .or_fail()?;
+
+    let rest = message_lines.collect::<Vec<_>>();
+    if !rest.is_empty() {
+        println!();
+        println!("{}", rest.join("\n"));
+    }
+    println!();
+    Ok(())
+}
+
+fn print_sarif(
+    reported: &[(DocumentUri, Vec<Diagnostic>)],
+    encoding: PositionEncoding,
+) -> orfail::Result<()> {
+    let base_dir = std::env::current_dir().or_fail()?;
+    let results = nojson::array(|f| {
+        for (uri, diagnostics) in reported {
+            let artifact_uri = sarif_artifact_uri(uri.path(), &base_dir);
+            let text = uri.read_to_string().unwrap_or_default();
+            for diagnostic in diagnostics {
+                f.element(nojson::object(|f| {
+                    if let Some(code) = &diagnostic.code {
+                        f.member("ruleId", code)?;
+                    }
+                    let level = match diagnostic.severity() {
+                        DiagnosticSeverity::Error => "error",
+                        DiagnosticSeverity::Warning => "warning",
+                        DiagnosticSeverity::Information | DiagnosticSeverity::Hint => "note",
+                    };
+                    f.member("level", level)?;
+                    f.member(
+                        "message",
+                        nojson::object(|f| f.member("text", &diagnostic.message)),
+                    )?;
+                    f.member(
+                        "locations",
+                        [nojson::object(|f| {
+                            f.member(
+                                "physicalLocation",
+                                nojson::object(|f| {
+                                    f.member(
+                                        "artifactLocation",
+                                        nojson::object(|f| f.member("uri", &artifact_uri)),
+                                    )?;
+                                    f.member(
+                                        "region",
+                                        sarif_region(&text, diagnostic.range, encoding),
+                                    )
+                                }),
+                            )
+                        })],
+                    )?;
+                    if let Some(source) = &diagnostic.source {
+                        f.member("properties", nojson::object(|f| f.member("source", source)))?;
+                    }
+                    Ok(())
+                }))?;
+            }
+        }
+        Ok(())
+    });
+
+    let sarif = nojson::json(|f| {
+        f.set_indent_size(2);
+        f.set_spacing(true);
+        f.object(|f| {
+            f.member("$schema", "https://json.schemastore.org/sarif-2.1.0.json")?;
+            f.member("version", "2.1.0")?;
+            f.member(
+                "runs",
+                [nojson::object(|f| {
+                    f.member(
+                        "tool",
+                        nojson::object(|f| {
+                            f.member(
+                                "driver",
+                                nojson::object(|f| {
+                                    f.member("name", env!("CARGO_PKG_NAME"))?;
+                                    f.member("version", env!("CARGO_PKG_VERSION"))
+                                }),
+                            )
+                        }),
+                    )?;
+                    f.member("results", &results)
+                })],
+            )
+        })
+    });
+    println!("{sarif}");
+    Ok(())
+}
+
+/// Returns a URI reference relative to `base_dir`, or a `file://` URI for paths outside of it
+fn sarif_artifact_uri(path: &Path, base_dir: &Path) -> String {
+    let (prefix, path) = match path.strip_prefix(base_dir) {
+        Ok(relative) => ("", relative),
+        Err(_) => ("file://", path),
+    };
+    let mut uri = prefix.to_owned();
+    for &b in path.to_string_lossy().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~".contains(&b) {
+            uri.push(char::from(b));
+        } else {
+            uri.push_str(&format!("%{b:02X}"));
+        }
+    }
+    uri
+}
+
+fn sarif_region(
+    text: &str,
+    range: PositionRange,
+    encoding: PositionEncoding,
+) -> impl nojson::DisplayJson {
+    // SARIF lines and columns are 1-based, and columns count UTF-16 code units by default
+    let range = range
+        .decode(text, encoding)
+        .encode(text, PositionEncoding::Utf16);
+    nojson::object(move |f| {
+        f.member("startLine", range.start.line + 1)?;
+        f.member("startColumn", range.start.character + 1)?;
+        f.member("endLine", range.end.line + 1)?;
+        f.member("endColumn", range.end.character + 1)
+    })
+}
@@ -0,0 +1,130 @@
+use orfail::OrFail;
+
+use crate::{
+    args::RAW_FLAG,
+    json::JsonObject,
+    lsp::{DocumentUri, Position, PositionRange},
+    proxy_client::{PORT_OPT, ProxyClient},
+    target::FILE_ARG,
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("folding")
+        .doc("List the folding ranges of a file (textDocument/foldingRange)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let file: DocumentUri = FILE_ARG
+        .take(&mut args)
+        .then(|a| DocumentUri::new(a.value()))?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    file.check_existence().or_fail()?;
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    let params =
+        nojson::object(|f| f.member("textDocument", nojson::object(|f| f.member("uri", &file))));
+    let result = client.call("textDocument/foldingRange", params).or_fail()?;
+
+    if raw {
+        println!("{result}");
+        return Ok(None);
+    }
+
+    let mut ranges = Option::<Vec<FoldingRange>>::try_from(result.value())
+        .or_fail()?
+        .unwrap_or_default();
+    if ranges.is_empty() {
+        println!("Not found");
+        return Ok(None);
+    }
+    ranges.sort_by_key(|r| (r.start_line, std::cmp::Reverse(r.end_line)));
+
+    let text = file.read_to_string().or_fail()?;
+    for (i, range) in ranges.iter().enumerate() {
+        print!(
+            "## {}. Lines {}-{}",
+            i + 1,
+            range.start_line + 1,
+            range.end_line + 1
+        );
+        if let Some(kind) = &range.kind {
+            print!(" ({kind})");
+        }
+        match &range.collapsed_text {
+            Some(collapsed_text) => println!(": `{collapsed_text}`"),
+            None => println!(),
+        }
+
+        let range_text = range
+            .to_position_range()
+            .get_range_text(&text, encoding)
+            .or_fail()?;
+        println!();
+        println!("```");
+        println!("{}", range_text.trim_end_matches('\n'));
+        println!("```");
+        println!();
+    }
+
+    Ok(None)
+}
+
+#[derive(Debug, Clone)]
+struct FoldingRange {
+    start_line: usize,
+    start_character: Option<usize>,
+    end_line: usize,
+    end_character: Option<usize>,
+    kind: Option<String>,
+    collapsed_text: Option<String>,
+}
+
+impl FoldingRange {
+    /// Returns the covered range (whole lines unless the characters are specified)
+    fn to_position_range(&self) -> PositionRange {
+        let end = match self.end_character {
+            Some(character) => Position {
+                line: self.end_line,
+                character,
+            },
+            None => Position {
+                line: self.end_line + 1,
+                character: 0,
+            },
+        };
+        PositionRange {
+            start: Position {
+                line: self.start_line,
+                character: self.start_character.unwrap_or(0),
+            },
+            end,
+        }
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for FoldingRange {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            start_line: object.convert_required("startLine")?,
+            start_character: object.convert_optional("startCharacter")?,
+            end_line: object.convert_required("endLine")?,
+            end_character: object.convert_optional("endCharacter")?,
+            kind: object.convert_optional("kind")?,
+            collapsed_text: object.convert_optional("collapsedText")?,
+        })
+    }
+}
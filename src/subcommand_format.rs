@@ -0,0 +1,117 @@
+use std::io::Write;
+
+use orfail::OrFail;
+
+use crate::{
+    args::{APPLY_FLAG, RAW_FLAG},
+    document::{
+        self, DocumentChange, DocumentChanges, TextDocument, TextDocumentChange, TextEdit,
+        print_markdown_changes,
+    },
+    proxy_client::{PORT_OPT, ProxyClient},
+    target::{TARGET_RANGE_ARG, TargetRange},
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("format")
+        .doc("Format a file or a range (textDocument/formatting, textDocument/rangeFormatting)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let tab_size: u32 = noargs::opt("tab-size")
+        .short('t')
+        .ty("INTEGER")
+        .default("4")
+        .doc("Size of a tab in spaces")
+        .take(&mut args)
+        .then(|a| a.value().parse())?;
+    let use_tabs = noargs::flag("use-tabs")
+        .doc("Prefer tabs over spaces for indentation")
+        .take(&mut args)
+        .is_present();
+    let apply = APPLY_FLAG.take(&mut args).is_present();
+    let check = noargs::flag("check")
+        .doc("Exit with status 1 if formatting would change the file")
+        .take(&mut args)
+        .is_present();
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let target: TargetRange = TARGET_RANGE_ARG
+        .take(&mut args)
+        .then(|a| a.value().parse())?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    target.file.check_existence().or_fail()?;
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    let text = target.file.read_to_string().or_fail()?;
+    let range = target.range.map(|range| range.encode(&text, encoding));
+    let options = nojson::object(|f| {
+        f.member("tabSize", tab_size)?;
+        f.member("insertSpaces", !use_tabs)
+    });
+    let params = nojson::object(|f| {
+        f.member(
+            "textDocument",
+            nojson::object(|f| f.member("uri", &target.file)),
+        )?;
+        if let Some(range) = range {
+            f.member("range", range)?;
+        }
+        f.member("options", &options)
+    });
+    let method = if range.is_some() {
+        "textDocument/rangeFormatting"
+    } else {
+        "textDocument/formatting"
+    };
+    let result = client.call(method, params).or_fail()?;
+    if raw {
+        println!("{result}");
+    }
+
+    let edits = Option::<Vec<TextEdit>>::try_from(result.value()).or_fail()?;
+    let edits = edits.unwrap_or_default();
+
+    let formatted =
+        document::apply_text_edits(&text, &edits.iter().collect::<Vec<_>>(), encoding).or_fail()?;
+    let changed = formatted != text;
+
+    let document_changes = DocumentChanges {
+        changes: vec![DocumentChange::TextDocument(TextDocumentChange {
+            text_document: TextDocument {
+                uri: target.file.clone(),
+                version: None,
+            },
+            edits,
+        })],
+        ..Default::default()
+    };
+    if !raw {
+        if changed {
+            print_markdown_changes("Format Changes", &document_changes, !apply, encoding);
+        } else {
+            println!("Already formatted");
+        }
+    }
+
+    if apply && changed {
+        document_changes.apply(encoding).or_fail()?;
+        eprintln!("=> Formatted");
+    }
+
+    if check && changed {
+        std::io::stdout().flush().or_fail()?;
+        std::process::exit(1);
+    }
+
+    Ok(None)
+}
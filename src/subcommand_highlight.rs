@@ -0,0 +1,124 @@
+use std::num::NonZeroUsize;
+
+use orfail::OrFail;
+
+use crate::{
+    args::{CONTEXT_OPT, RAW_FLAG},
+    json::JsonObject,
+    location,
+    lsp::PositionRange,
+    proxy_client::{PORT_OPT, ProxyClient},
+    target::{TARGET_ARG, TargetLocation},
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("highlight")
+        .doc("List the occurrences of a symbol in a file (textDocument/documentHighlight)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let context_lines: NonZeroUsize = CONTEXT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let target: TargetLocation = TARGET_ARG.take(&mut args).then(|a| a.value().parse())?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    target.file.check_existence().or_fail()?;
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    let text = target.file.read_to_string().or_fail()?;
+    let params = nojson::object(|f| target.fmt_json_object(f, &text, encoding));
+    let result = client
+        .call("textDocument/documentHighlight", params)
+        .or_fail()?;
+
+    if raw {
+        println!("{result}");
+        return Ok(None);
+    }
+
+    let mut highlights = Option::<Vec<DocumentHighlight>>::try_from(result.value())
+        .or_fail()?
+        .unwrap_or_default();
+    if highlights.is_empty() {
+        println!("Not found");
+        return Ok(None);
+    }
+    highlights.sort_by_key(|h| (h.range.start, h.range.end));
+
+    for (i, highlight) in highlights.iter().enumerate() {
+        let symbol = highlight.range.get_range_text(&text, encoding).or_fail()?;
+        println!("## {} {}: `{symbol}`", highlight.kind, i + 1);
+        println!();
+        location::print_source_excerpt(
+            &target.file,
+            &text,
+            highlight.range,
+            context_lines.get(),
+            encoding,
+            true,
+        )
+        .or_fail()?;
+        println!();
+    }
+
+    Ok(None)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DocumentHighlightKind {
+    Text = 1,
+    Read = 2,
+    Write = 3,
+}
+
+impl std::fmt::Display for DocumentHighlightKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text => write!(f, "Text"),
+            Self::Read => write!(f, "Read"),
+            Self::Write => write!(f, "Write"),
+        }
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for DocumentHighlightKind {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        match u8::try_from(value)? {
+            1 => Ok(Self::Text),
+            2 => Ok(Self::Read),
+            3 => Ok(Self::Write),
+            kind => Err(value.invalid(format!("unknown document highlight kind: {kind}"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DocumentHighlight {
+    range: PositionRange,
+    kind: DocumentHighlightKind,
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for DocumentHighlight {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            range: object.convert_required("range")?,
+            kind: object
+                .convert_optional("kind")?
+                .unwrap_or(DocumentHighlightKind::Text),
+        })
+    }
+}
@@ -28,8 +28,10 @@ pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawAr
     target.file.check_existence().or_fail()?;
 
     let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
 
-    let params = nojson::object(|f| target.fmt_json_object(f));
+    let text = target.file.read_to_string().or_fail()?;
+    let params = nojson::object(|f| target.fmt_json_object(f, &text, encoding));
     let result = client.call("textDocument/hover", params).or_fail()?;
 
     if raw {
@@ -47,8 +49,7 @@ pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawAr
     let contents: JsonObject<'_, '_> = object.convert_required("contents").or_fail()?;
     let description: String = contents.convert_required("value").or_fail()?;
 
-    let text = target.file.read_to_string().or_fail()?;
-    let symbol = range.get_range_text(&text).or_fail()?;
+    let symbol = range.get_range_text(&text, encoding).or_fail()?;
 
     println!("# `{symbol}`\n");
     println!("{description}");
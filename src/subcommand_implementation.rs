@@ -0,0 +1,11 @@
+use crate::location;
+
+pub fn try_run(args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    location::try_run_subcommand(
+        args,
+        noargs::cmd("implementation")
+            .doc("Get implementation locations for a symbol (textDocument/implementation)"),
+        "textDocument/implementation",
+        "Implementation",
+    )
+}
@@ -0,0 +1,244 @@
+use orfail::OrFail;
+
+use crate::{
+    args::RAW_FLAG,
+    json::JsonObject,
+    lsp::{MarkupText, Position},
+    proxy_client::{PORT_OPT, ProxyClient, Resolvable},
+    target::{TARGET_LINES_ARG, TargetLines},
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("inlay-hints")
+        .doc("Show a file with inlay hints inlined (textDocument/inlayHint)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let tooltips = noargs::flag("tooltips")
+        .short('t')
+        .doc("Show hint tooltips (resolving them with inlayHint/resolve if needed)")
+        .take(&mut args)
+        .is_present();
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let target: TargetLines = TARGET_LINES_ARG
+        .take(&mut args)
+        .then(|a| a.value().parse())?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    target.file.check_existence().or_fail()?;
+
+    let text = target.file.read_to_string().or_fail()?;
+    let range = target.to_range(&text);
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    let params = nojson::object(|f| {
+        f.member(
+            "textDocument",
+            nojson::object(|f| f.member("uri", &target.file)),
+        )?;
+        f.member("range", range)
+    });
+    let result = client.call("textDocument/inlayHint", params).or_fail()?;
+
+    if raw {
+        println!("{result}");
+        return Ok(None);
+    }
+
+    let mut hints = Option::<Vec<InlayHint>>::try_from(result.value())
+        .or_fail()?
+        .unwrap_or_default();
+    if tooltips {
+        client.resolve_items("inlayHint/resolve", &mut hints);
+    }
+    for hint in &mut hints {
+        hint.position = hint.position.decode(&text, encoding);
+    }
+    hints.sort_by_key(|h| h.position);
+
+    let base_dir = std::env::current_dir().or_fail()?;
+    println!(
+        "# Inlay Hints: {} ({} hints)\n",
+        target.file.relative_path(&base_dir).display(),
+        hints.len()
+    );
+
+    let lines = text.lines().collect::<Vec<_>>();
+    let end_line = range.end.line.min(lines.len());
+    let width = end_line.to_string().len();
+    println!("```");
+    for (i, line) in lines
+        .iter()
+        .enumerate()
+        .take(end_line)
+        .skip(range.start.line)
+    {
+        let line_hints = hints.iter().filter(|h| h.position.line == i);
+        println!("{:>width$}| {}", i + 1, splice_hints(line, line_hints));
+    }
+    println!("```");
+
+    if tooltips {
+        let mut printed_header = false;
+        for hint in &hints {
+            let mut entries = Vec::new();
+            if let Some(tooltip) = &hint.tooltip {
+                entries.push((hint.label.text(), tooltip));
+            }
+            if let InlayHintLabel::Parts(parts) = &hint.label {
+                for part in parts {
+                    if let Some(tooltip) = &part.tooltip {
+                        entries.push((part.value.clone(), tooltip));
+                    }
+                }
+            }
+            for (label, tooltip) in entries {
+                if !printed_header {
+                    println!("\n## Tooltips\n");
+                    printed_header = true;
+                }
+                let mut tooltip_lines = tooltip.trim_end().lines();
+                println!(
+                    "- {}:{} `{}`: {}",
+                    hint.position.line + 1,
+                    hint.position.character + 1,
+                    label.trim(),
+                    tooltip_lines.next().unwrap_or_default()
+                );
+                for line in tooltip_lines {
+                    println!("  {line}");
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Inserts the labels of `hints` into `line` at their positions
+fn splice_hints<'a>(line: &str, hints: impl Iterator<Item = &'a InlayHint>) -> String {
+    let mut hints = hints.peekable();
+    let mut output = String::new();
+    for (character, ch) in line.chars().chain(std::iter::once('\n')).enumerate() {
+        while let Some(hint) = hints.next_if(|h| h.position.character <= character) {
+            output.push_str(&hint.padded_label());
+        }
+        if ch != '\n' {
+            output.push(ch);
+        }
+    }
+    for hint in hints {
+        output.push_str(&hint.padded_label());
+    }
+    output
+}
+
+#[derive(Debug, Clone)]
+struct InlayHint {
+    position: Position,
+    label: InlayHintLabel,
+    tooltip: Option<String>,
+    padding_left: bool,
+    padding_right: bool,
+    json: nojson::RawJsonOwned,
+}
+
+impl InlayHint {
+    fn padded_label(&self) -> String {
+        format!(
+            "{}{}{}",
+            if self.padding_left { " " } else { "" },
+            self.label.text(),
+            if self.padding_right { " " } else { "" }
+        )
+    }
+
+    fn has_tooltip(&self) -> bool {
+        self.tooltip.is_some()
+            || matches!(&self.label, InlayHintLabel::Parts(parts) if parts.iter().any(|p| p.tooltip.is_some()))
+    }
+}
+
+impl Resolvable for InlayHint {
+    fn is_resolved(&self) -> bool {
+        self.has_tooltip()
+    }
+
+    fn json(&self) -> &nojson::RawJsonOwned {
+        &self.json
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for InlayHint {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            position: object.convert_required("position")?,
+            label: object.convert_required("label")?,
+            tooltip: object
+                .convert_optional::<MarkupText>("tooltip")?
+                .map(|t| t.0),
+            padding_left: object.convert_optional_or_default("paddingLeft")?,
+            padding_right: object.convert_optional_or_default("paddingRight")?,
+            json: value.extract().into_owned(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum InlayHintLabel {
+    String(String),
+    Parts(Vec<InlayHintLabelPart>),
+}
+
+impl InlayHintLabel {
+    fn text(&self) -> String {
+        match self {
+            Self::String(s) => s.clone(),
+            Self::Parts(parts) => parts.iter().map(|p| p.value.as_str()).collect(),
+        }
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for InlayHintLabel {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        if value.kind().is_string() {
+            Ok(Self::String(value.try_into()?))
+        } else {
+            Ok(Self::Parts(value.try_into()?))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct InlayHintLabelPart {
+    value: String,
+    tooltip: Option<String>,
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for InlayHintLabelPart {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            value: object.convert_required("value")?,
+            tooltip: object
+                .convert_optional::<MarkupText>("tooltip")?
+                .map(|t| t.0),
+        })
+    }
+}
@@ -0,0 +1,109 @@
+use orfail::OrFail;
+
+use crate::{
+    args::RAW_FLAG,
+    json::JsonObject,
+    lsp::{DocumentUri, PositionRange},
+    proxy_client::{PORT_OPT, ProxyClient, Resolvable},
+    target::FILE_ARG,
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("links")
+        .doc("List the document links of a file (textDocument/documentLink)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let file: DocumentUri = FILE_ARG
+        .take(&mut args)
+        .then(|a| DocumentUri::new(a.value()))?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    file.check_existence().or_fail()?;
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    let params =
+        nojson::object(|f| f.member("textDocument", nojson::object(|f| f.member("uri", &file))));
+    let result = client.call("textDocument/documentLink", params).or_fail()?;
+
+    if raw {
+        println!("{result}");
+        return Ok(None);
+    }
+
+    let mut links = Option::<Vec<DocumentLink>>::try_from(result.value())
+        .or_fail()?
+        .unwrap_or_default();
+    if links.is_empty() {
+        println!("Not found");
+        return Ok(None);
+    }
+
+    client.resolve_items("documentLink/resolve", &mut links);
+    links.sort_by_key(|l| (l.range.start, l.range.end));
+
+    let text = file.read_to_string().or_fail()?;
+    let base_dir = std::env::current_dir().or_fail()?;
+    let path = file.relative_path(&base_dir);
+    for (i, link) in links.iter().enumerate() {
+        let link_text = link.range.get_range_text(&text, encoding).or_fail()?;
+        let start = link.range.start.decode(&text, encoding);
+        println!(
+            "{}. {}:{}:{}: `{link_text}` -> {}",
+            i + 1,
+            path.display(),
+            start.line + 1,
+            start.character + 1,
+            link.target.as_deref().unwrap_or("(unresolved)")
+        );
+        if let Some(tooltip) = &link.tooltip {
+            println!("   {tooltip}");
+        }
+    }
+
+    Ok(None)
+}
+
+#[derive(Debug, Clone)]
+struct DocumentLink {
+    range: PositionRange,
+
+    /// Any URI (not necessarily `file://`)
+    target: Option<String>,
+    tooltip: Option<String>,
+    json: nojson::RawJsonOwned,
+}
+
+impl Resolvable for DocumentLink {
+    fn is_resolved(&self) -> bool {
+        self.target.is_some()
+    }
+
+    fn json(&self) -> &nojson::RawJsonOwned {
+        &self.json
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for DocumentLink {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            range: object.convert_required("range")?,
+            target: object.convert_optional("target")?,
+            tooltip: object.convert_optional("tooltip")?,
+            json: value.extract().into_owned(),
+        })
+    }
+}
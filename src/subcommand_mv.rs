@@ -0,0 +1,49 @@
+use orfail::OrFail;
+
+use crate::{
+    args::{APPLY_FLAG, RAW_FLAG},
+    document::RenameFileChange,
+    file_operation::FileOperation,
+    lsp::DocumentUri,
+    proxy_client::{PORT_OPT, ProxyClient},
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("mv")
+        .doc("Rename a file and update its references (workspace/willRenameFiles)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let apply = APPLY_FLAG.take(&mut args).is_present();
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let old_uri: DocumentUri = noargs::arg("OLD")
+        .example("/path/to/old_file")
+        .doc("File to rename")
+        .take(&mut args)
+        .then(|a| DocumentUri::new(a.value()))?;
+    let new_uri: DocumentUri = noargs::arg("NEW")
+        .example("/path/to/new_file")
+        .doc("New path of the file")
+        .take(&mut args)
+        .then(|a| DocumentUri::new(a.value()))?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let operation = FileOperation::Rename(RenameFileChange {
+        old_uri,
+        new_uri,
+        options: Default::default(),
+        annotation_id: None,
+    });
+    operation.run(&mut client, apply, raw).or_fail()?;
+
+    Ok(None)
+}
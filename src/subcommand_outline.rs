@@ -0,0 +1,99 @@
+use orfail::OrFail;
+
+use crate::{
+    args::RAW_FLAG,
+    lsp::{DocumentUri, PositionRange},
+    proxy_client::{PORT_OPT, ProxyClient},
+    symbol::{DocumentSymbol, DocumentSymbols, WorkspaceSymbolLocation},
+    target::FILE_ARG,
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("outline")
+        .doc("Show the symbol tree of a file (textDocument/documentSymbol)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let depth: Option<usize> = noargs::opt("depth")
+        .short('d')
+        .ty("INTEGER")
+        .doc("Maximum depth of nested symbols to show (unlimited if omitted)")
+        .take(&mut args)
+        .present_and_then(|a| a.value().parse())?;
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let file: DocumentUri = FILE_ARG
+        .take(&mut args)
+        .then(|a| DocumentUri::new(a.value()))?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    file.check_existence().or_fail()?;
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+
+    let params =
+        nojson::object(|f| f.member("textDocument", nojson::object(|f| f.member("uri", &file))));
+    let result = client
+        .call("textDocument/documentSymbol", params)
+        .or_fail()?;
+
+    if raw {
+        println!("{result}");
+        return Ok(None);
+    }
+
+    match DocumentSymbols::try_from(result.value()).or_fail()? {
+        DocumentSymbols::Hierarchical(symbols) if symbols.is_empty() => {
+            println!("Not found");
+        }
+        DocumentSymbols::Hierarchical(symbols) => {
+            print_symbol_tree(&symbols, 0, depth);
+        }
+        DocumentSymbols::Flat(symbols) if symbols.is_empty() => {
+            println!("Not found");
+        }
+        DocumentSymbols::Flat(symbols) => {
+            for symbol in symbols {
+                print!("- {} `{}`", symbol.kind, symbol.name);
+                if let Some(container_name) = symbol.container_name.filter(|s| !s.is_empty()) {
+                    print!(" (in `{container_name}`)");
+                }
+                if let WorkspaceSymbolLocation::Location(location) = symbol.location {
+                    print!(" {}", fmt_line_range(location.range));
+                }
+                println!();
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+fn print_symbol_tree(symbols: &[DocumentSymbol], level: usize, depth: Option<usize>) {
+    if depth.is_some_and(|depth| level >= depth) {
+        return;
+    }
+
+    for symbol in symbols {
+        print!("{}- {} `{}`", "  ".repeat(level), symbol.kind, symbol.name);
+        if let Some(detail) = symbol.detail.as_ref().filter(|s| !s.is_empty()) {
+            print!(": `{detail}`");
+        }
+        println!(" {}", fmt_line_range(symbol.range));
+        print_symbol_tree(&symbol.children, level + 1, depth);
+    }
+}
+
+fn fmt_line_range(range: PositionRange) -> String {
+    if range.is_multiline() {
+        format!("(L{}-L{})", range.start.line + 1, range.end.line + 1)
+    } else {
+        format!("(L{})", range.start.line + 1)
+    }
+}
@@ -0,0 +1,93 @@
+use std::{collections::BTreeMap, num::NonZeroUsize};
+
+use orfail::OrFail;
+
+use crate::{
+    args::{CONTEXT_OPT, RAW_FLAG},
+    location::{self, Location, Locations},
+    lsp::DocumentUri,
+    proxy_client::{PORT_OPT, ProxyClient},
+    target::{TARGET_ARG, TargetLocation},
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("references")
+        .doc("Find all references to a symbol (textDocument/references)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let context_lines: NonZeroUsize = CONTEXT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let include_declaration = noargs::flag("include-declaration")
+        .short('d')
+        .doc("Include the declaration of the symbol in the results")
+        .take(&mut args)
+        .is_present();
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let target: TargetLocation = TARGET_ARG.take(&mut args).then(|a| a.value().parse())?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    target.file.check_existence().or_fail()?;
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    let target_text = target.file.read_to_string().or_fail()?;
+    let params = nojson::object(|f| {
+        target.fmt_json_object(f, &target_text, encoding)?;
+        f.member(
+            "context",
+            nojson::object(|f| f.member("includeDeclaration", include_declaration)),
+        )
+    });
+    let result = client.call("textDocument/references", params).or_fail()?;
+
+    if raw {
+        println!("{result}");
+        return Ok(None);
+    }
+
+    let Locations(references) = Locations::try_from(result.value()).or_fail()?;
+    if references.is_empty() {
+        println!("Not found");
+        return Ok(None);
+    }
+
+    // Group references by file
+    let mut files: BTreeMap<DocumentUri, Vec<Location>> = BTreeMap::new();
+    for reference in references {
+        files
+            .entry(reference.uri.clone())
+            .or_default()
+            .push(reference);
+    }
+
+    let base_dir = std::env::current_dir().or_fail()?;
+    for (uri, mut references) in files {
+        references.sort_by_key(|r| r.range.start);
+
+        let text = uri.read_to_string().or_fail()?;
+        println!("## {}", uri.relative_path(&base_dir).display());
+        println!();
+        for reference in references {
+            location::print_source_excerpt(
+                &uri,
+                &text,
+                reference.range,
+                context_lines.get(),
+                encoding,
+                false,
+            )
+            .or_fail()?;
+            println!();
+        }
+    }
+
+    Ok(None)
+}
@@ -2,7 +2,10 @@ use orfail::OrFail;
 
 use crate::{
     args::{APPLY_FLAG, RAW_FLAG},
-    document::{DocumentChange, DocumentChanges},
+    document::{DocumentChanges, print_markdown_changes},
+    json::JsonObject,
+    lsp::{PositionEncoding, PositionRange},
+    lsp_server::GET_SERVER_CAPABILITIES_METHOD,
     proxy_client::{PORT_OPT, ProxyClient},
     target::{TARGET_ARG, TargetLocation},
 };
@@ -18,24 +21,41 @@ pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawAr
 
     let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
     let apply = APPLY_FLAG.take(&mut args).is_present();
+    let check_only = noargs::flag("check-only")
+        .doc("Only check whether the target can be renamed (textDocument/prepareRename)")
+        .take(&mut args)
+        .is_present();
     let raw = RAW_FLAG.take(&mut args).is_present();
     let target: TargetLocation = TARGET_ARG.take(&mut args).then(|a| a.value().parse())?;
-    let new_name: String = noargs::arg("NEW_NAME")
-        .doc("New name for the symbol being renamed")
+    let new_name: Option<String> = noargs::arg("[NEW_NAME]")
+        .doc("New name for the symbol being renamed (required unless --check-only)")
         .example("new-name")
         .take(&mut args)
-        .then(|a| a.value().parse())?;
+        .present_and_then(|a| a.value().parse())?;
 
     if let Some(help) = args.finish()? {
         print!("{help}");
         return Ok(None);
     }
     target.file.check_existence().or_fail()?;
+    (check_only || new_name.is_some())
+        .or_fail_with(|()| "NEW_NAME is required unless --check-only is specified".to_owned())?;
 
     let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+    let text = target.file.read_to_string().or_fail()?;
+
+    if supports_prepare_rename(&mut client).or_fail()? {
+        prepare_rename(&mut client, &target, &text, encoding, raw, check_only).or_fail()?;
+    } else if check_only {
+        eprintln!("[WARN] LSP server does not support textDocument/prepareRename");
+    }
+    let Some(new_name) = new_name.filter(|_| !check_only) else {
+        return Ok(None);
+    };
 
     let params = nojson::object(|f| {
-        target.fmt_json_object(f)?;
+        target.fmt_json_object(f, &text, encoding)?;
         f.member("newName", &new_name)
     });
     let result = client.call("textDocument/rename", params).or_fail()?;
@@ -46,73 +66,119 @@ pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawAr
     let document_changes = DocumentChanges::try_from(result.value())
         .or_fail_with(|e| format!("Failed to parse document changes: {e}"))?;
     if !raw {
-        print_markdown_changes(&document_changes, !apply);
+        print_markdown_changes("Rename Changes", &document_changes, !apply, encoding);
     }
 
     if apply {
-        document_changes.apply().or_fail()?;
+        document_changes.apply(encoding).or_fail()?;
         eprintln!("=> Renamed");
     }
 
     Ok(None)
 }
 
-fn print_markdown_changes(document_changes: &DocumentChanges, dry_run: bool) {
-    let base_dir = std::env::current_dir().unwrap_or_default();
-
-    println!(
-        "# Rename Changes{}\n",
-        if dry_run { " (dry-run)" } else { "" }
-    );
-
-    for change in &document_changes.changes {
-        match change {
-            DocumentChange::TextDocument(text_change) => {
-                let Ok(text) = text_change.text_document.uri.read_to_string() else {
-                    continue;
-                };
-                let path = text_change.text_document.uri.relative_path(&base_dir);
-
-                println!("## {}\n", path.display());
-
-                for edit in &text_change.edits {
-                    println!(
-                        "### Line {}, Character {}\n",
-                        edit.range.start.line + 1,
-                        edit.range.start.character + 1
-                    );
-
-                    if edit.range.is_multiline() {
-                        println!("```diff");
-                        println!("- [multiline change]");
-                        println!("+ {}", edit.new_text);
-                        println!("```\n");
-                    } else {
-                        let old_line = edit.range.get_start_line(&text).unwrap_or_default();
-                        let new_line = old_line
-                            .chars()
-                            .take(edit.range.start.character)
-                            .chain(edit.new_text.chars())
-                            .chain(old_line.chars().skip(edit.range.end.character))
-                            .collect::<String>();
-
-                        println!("```diff");
-                        println!("- {old_line}");
-                        println!("+ {new_line}");
-                        println!("```\n");
-                    }
-                }
-            }
-            DocumentChange::RenameFile(rename_change) => {
-                let old_path = rename_change.old_uri.relative_path(&base_dir);
-                let new_path = rename_change.new_uri.relative_path(&base_dir);
-
-                println!("## File Rename\n");
-                println!("```diff");
-                println!("- {}", old_path.display());
-                println!("+ {}", new_path.display());
-                println!("```\n");
+/// Returns whether `renameProvider.prepareProvider` is enabled in the server capabilities
+fn supports_prepare_rename(client: &mut ProxyClient) -> orfail::Result<bool> {
+    let capabilities = client.call(GET_SERVER_CAPABILITIES_METHOD, ()).or_fail()?;
+    let Some(provider) = JsonObject::new(capabilities.value())
+        .or_fail()?
+        .get_optional("renameProvider")
+    else {
+        return Ok(false);
+    };
+    if provider.kind().is_bool() {
+        // `true` means that rename is supported but prepareRename is not
+        return Ok(false);
+    }
+    JsonObject::new(provider)
+        .and_then(|provider| provider.convert_optional_or_default("prepareProvider"))
+        .or_fail()
+}
+
+/// Runs textDocument/prepareRename and prints the range that will be renamed
+///
+/// In raw mode, the result is printed only if `check_only` is set so that the output of a rename
+/// consists of the textDocument/rename result alone.
+fn prepare_rename(
+    client: &mut ProxyClient,
+    target: &TargetLocation,
+    text: &str,
+    encoding: PositionEncoding,
+    raw: bool,
+    check_only: bool,
+) -> orfail::Result<()> {
+    let base_dir = std::env::current_dir().or_fail()?;
+    let path = target.file.relative_path(&base_dir);
+    let location = format!("{}:{}:{}", path.display(), target.line, target.character);
+    let params = nojson::object(|f| target.fmt_json_object(f, text, encoding));
+    let result = client
+        .call("textDocument/prepareRename", params)
+        .or_fail_with(|e| format!("Cannot rename at {location}: {e}"))?;
+    if raw && check_only {
+        println!("{result}");
+    }
+
+    let result = Option::<PrepareRenameResult>::try_from(result.value()).or_fail()?;
+    let result = result
+        .or_fail_with(|()| format!("Cannot rename at {location}: the position is not renamable"))?;
+    if raw {
+        return Ok(());
+    }
+
+    println!("# Rename Target\n");
+    match result {
+        PrepareRenameResult::Range { range, placeholder } => {
+            let range_text = range.get_range_text(text, encoding).or_fail()?;
+            let range = range.decode(text, encoding);
+            println!(
+                "{}:{}:{}-{}:{}: `{range_text}`",
+                path.display(),
+                range.start.line + 1,
+                range.start.character + 1,
+                range.end.line + 1,
+                range.end.character + 1
+            );
+            if let Some(placeholder) = placeholder {
+                println!("Placeholder: `{placeholder}`");
             }
         }
+        PrepareRenameResult::DefaultBehavior => {
+            println!("{location}: (the identifier at the position)");
+        }
+    }
+    println!();
+    Ok(())
+}
+
+/// Result of textDocument/prepareRename
+#[derive(Debug, Clone)]
+enum PrepareRenameResult {
+    Range {
+        range: PositionRange,
+        placeholder: Option<String>,
+    },
+
+    /// `{ defaultBehavior: boolean }`: the client should use its own word rule
+    DefaultBehavior,
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for PrepareRenameResult {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        if object.get_optional("defaultBehavior").is_some() {
+            return Ok(Self::DefaultBehavior);
+        }
+        if let Some(range) = object.get_optional("range") {
+            return Ok(Self::Range {
+                range: range.try_into()?,
+                placeholder: object.convert_optional("placeholder")?,
+            });
+        }
+        Ok(Self::Range {
+            range: value.try_into()?,
+            placeholder: None,
+        })
     }
 }
@@ -0,0 +1,38 @@
+use orfail::OrFail;
+
+use crate::{
+    args::{APPLY_FLAG, RAW_FLAG},
+    file_operation::FileOperation,
+    lsp::DocumentUri,
+    proxy_client::{PORT_OPT, ProxyClient},
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("rm")
+        .doc("Delete a file and update related files (workspace/willDeleteFiles)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let apply = APPLY_FLAG.take(&mut args).is_present();
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let file: DocumentUri = noargs::arg("FILE")
+        .example("/path/to/file")
+        .doc("File to delete")
+        .take(&mut args)
+        .then(|a| DocumentUri::new(a.value()))?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let operation = FileOperation::Delete(file);
+    operation.run(&mut client, apply, raw).or_fail()?;
+
+    Ok(None)
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use orfail::OrFail;
+
+use crate::{
+    args::RAW_FLAG,
+    json::JsonObject,
+    lsp::{DocumentUri, Position, PositionRange},
+    proxy_client::{PORT_OPT, ProxyClient},
+    target::TargetLocation,
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("selection-range")
+        .doc("Show the nested expanding ranges at positions (textDocument/selectionRange)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let mut targets = Vec::new();
+    while let Some(target) = noargs::arg("TARGET...")
+        .example("/path/to/file:1:5")
+        .doc("Target locations (FILE:LINE:CHAR)")
+        .take(&mut args)
+        .present_and_then(|a| a.value().parse::<TargetLocation>())?
+    {
+        targets.push(target);
+        if args.metadata().help_mode {
+            break;
+        }
+    }
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    (!targets.is_empty()).or_fail_with(|()| "no target location is specified".to_owned())?;
+
+    for target in &targets {
+        target.file.check_existence().or_fail()?;
+    }
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    // A single request can only cover positions in the same document
+    let mut texts: BTreeMap<DocumentUri, String> = BTreeMap::new();
+    let mut positions_by_file: BTreeMap<DocumentUri, Vec<Position>> = BTreeMap::new();
+    for target in &targets {
+        if !texts.contains_key(&target.file) {
+            let text = target.file.read_to_string().or_fail()?;
+            texts.insert(target.file.clone(), text);
+        }
+        let position = target.to_position(&texts[&target.file], encoding);
+        positions_by_file
+            .entry(target.file.clone())
+            .or_default()
+            .push(position);
+    }
+    let base_dir = std::env::current_dir().or_fail()?;
+
+    let mut is_first = true;
+    for (file, positions) in &positions_by_file {
+        let params = nojson::object(|f| {
+            f.member("textDocument", nojson::object(|f| f.member("uri", file)))?;
+            f.member("positions", positions)
+        });
+        let result = client
+            .call("textDocument/selectionRange", params)
+            .or_fail()?;
+
+        if raw {
+            println!("{result}");
+            continue;
+        }
+
+        let selection_ranges = Option::<Vec<SelectionRange>>::try_from(result.value())
+            .or_fail()?
+            .unwrap_or_default();
+        let text = &texts[file];
+        for (i, position) in positions.iter().enumerate() {
+            if !is_first {
+                println!();
+            }
+            is_first = false;
+
+            let position = position.decode(text, encoding);
+            println!(
+                "# {}:{}:{}\n",
+                file.relative_path(&base_dir).display(),
+                position.line + 1,
+                position.character + 1
+            );
+            let Some(selection_range) = selection_ranges.get(i) else {
+                println!("Not found");
+                continue;
+            };
+
+            let mut current = Some(selection_range);
+            let mut level = 1;
+            while let Some(selection_range) = current {
+                let range_text = selection_range
+                    .range
+                    .get_range_text(text, encoding)
+                    .or_fail()?;
+                let range = selection_range.range.decode(text, encoding);
+                let label = format!(
+                    "{level}. {}:{}-{}:{}",
+                    range.start.line + 1,
+                    range.start.character + 1,
+                    range.end.line + 1,
+                    range.end.character + 1
+                );
+                if range.is_multiline() {
+                    println!("{label}:");
+                    println!("```");
+                    println!("{}", range_text.trim_end_matches('\n'));
+                    println!("```");
+                } else {
+                    println!("{label}: `{range_text}`");
+                }
+                current = selection_range.parent.as_deref();
+                level += 1;
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+#[derive(Debug, Clone)]
+struct SelectionRange {
+    range: PositionRange,
+    parent: Option<Box<SelectionRange>>,
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for SelectionRange {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            range: object.convert_required("range")?,
+            parent: object
+                .convert_optional::<SelectionRange>("parent")?
+                .map(Box::new),
+        })
+    }
+}
@@ -0,0 +1,246 @@
+use orfail::OrFail;
+
+use crate::{
+    args::RAW_FLAG,
+    json::JsonObject,
+    lsp::{Position, PositionEncoding},
+    lsp_server::GET_SERVER_CAPABILITIES_METHOD,
+    proxy_client::{PORT_OPT, ProxyClient},
+    target::{TARGET_LINES_ARG, TargetLines},
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("semantic-tokens")
+        .doc("Show the semantic tokens of a file (textDocument/semanticTokens/full, textDocument/semanticTokens/range)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let json = noargs::flag("json")
+        .short('j')
+        .doc("Print the decoded tokens as a JSON array")
+        .take(&mut args)
+        .is_present();
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let target: TargetLines = TARGET_LINES_ARG
+        .take(&mut args)
+        .then(|a| a.value().parse())?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    target.file.check_existence().or_fail()?;
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+
+    let capabilities = client.call(GET_SERVER_CAPABILITIES_METHOD, ()).or_fail()?;
+    let legend = SemanticTokensLegend::from_capabilities(capabilities.value())
+        .or_fail()?
+        .or_fail_with(|()| "LSP server does not support semantic tokens".to_owned())?;
+    let encoding = PositionEncoding::from_capabilities(capabilities.value()).or_fail()?;
+
+    let text = target.file.read_to_string().or_fail()?;
+    let lines_range = target.lines.map(|_| target.to_range(&text));
+
+    // Without range support, the whole file is requested and the tokens are filtered afterwards
+    let supports_range = supports_range_request(capabilities.value()).or_fail()?;
+    let request_range = lines_range.filter(|_| supports_range);
+    let params = nojson::object(|f| {
+        f.member(
+            "textDocument",
+            nojson::object(|f| f.member("uri", &target.file)),
+        )?;
+        if let Some(range) = request_range {
+            f.member("range", range)?;
+        }
+        Ok(())
+    });
+    let method = if request_range.is_some() {
+        "textDocument/semanticTokens/range"
+    } else {
+        "textDocument/semanticTokens/full"
+    };
+    let result = client.call(method, params).or_fail()?;
+
+    if raw {
+        println!("{result}");
+        return Ok(None);
+    }
+
+    let data: Vec<u32> = if result.value().kind().is_null() {
+        Vec::new()
+    } else {
+        JsonObject::new(result.value())
+            .and_then(|result| result.convert_required("data"))
+            .or_fail()?
+    };
+    let mut tokens = decode_tokens(&data, &legend).or_fail()?;
+    if let Some(range) = lines_range {
+        tokens.retain(|token| (range.start.line..range.end.line).contains(&token.line));
+    }
+
+    for token in &mut tokens {
+        let start = Position {
+            line: token.line,
+            character: token.character,
+        };
+        let end = Position {
+            character: token.character + token.length,
+            ..start
+        };
+        token.character = start.decode(&text, encoding).character;
+        token.length = end.decode(&text, encoding).character - token.character;
+    }
+
+    let lines = text.lines().collect::<Vec<_>>();
+    if json {
+        let tokens = nojson::array(|f| {
+            for token in &tokens {
+                f.element(nojson::object(|f| {
+                    f.member("line", token.line)?;
+                    f.member("character", token.character)?;
+                    f.member("length", token.length)?;
+                    f.member("type", token.token_type)?;
+                    f.member("modifiers", &token.modifiers)?;
+                    f.member("text", token.text(&lines))
+                }))?;
+            }
+            Ok(())
+        });
+        println!("{tokens}");
+        return Ok(None);
+    }
+
+    if tokens.is_empty() {
+        println!("Not found");
+        return Ok(None);
+    }
+    for token in &tokens {
+        println!(
+            "{}:{} {} {} [{}] `{}`",
+            token.line + 1,
+            token.character + 1,
+            token.length,
+            token.token_type,
+            token.modifiers.join(","),
+            token.text(&lines)
+        );
+    }
+
+    Ok(None)
+}
+
+/// Returns whether `semanticTokensProvider.range` is enabled in `ServerCapabilities`
+fn supports_range_request(
+    capabilities: nojson::RawJsonValue<'_, '_>,
+) -> Result<bool, nojson::JsonParseError> {
+    let Some(provider) = JsonObject::new(capabilities)?.get_optional("semanticTokensProvider")
+    else {
+        return Ok(false);
+    };
+    let Some(range) = JsonObject::new(provider)?.get_optional("range") else {
+        return Ok(false);
+    };
+    // `range` is either a boolean or an empty object
+    if range.kind().is_bool() {
+        return range.try_into();
+    }
+    Ok(true)
+}
+
+#[derive(Debug)]
+struct SemanticTokensLegend {
+    token_types: Vec<String>,
+    token_modifiers: Vec<String>,
+}
+
+impl SemanticTokensLegend {
+    /// Extracts `semanticTokensProvider.legend` from `ServerCapabilities`
+    fn from_capabilities(
+        capabilities: nojson::RawJsonValue<'_, '_>,
+    ) -> Result<Option<Self>, nojson::JsonParseError> {
+        let Some(provider) = JsonObject::new(capabilities)?.get_optional("semanticTokensProvider")
+        else {
+            return Ok(None);
+        };
+        let legend = JsonObject::new(JsonObject::new(provider)?.get_required("legend")?)?;
+        Ok(Some(Self {
+            token_types: legend.convert_required("tokenTypes")?,
+            token_modifiers: legend.convert_required("tokenModifiers")?,
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct SemanticToken<'a> {
+    line: usize,
+    character: usize,
+    length: usize,
+    token_type: &'a str,
+    modifiers: Vec<&'a str>,
+}
+
+impl SemanticToken<'_> {
+    fn text(&self, lines: &[&str]) -> String {
+        lines
+            .get(self.line)
+            .map(|line| {
+                line.chars()
+                    .skip(self.character)
+                    .take(self.length)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Decodes the relative `[deltaLine, deltaStart, length, tokenType, tokenModifiers]` tuples
+fn decode_tokens<'a>(
+    data: &[u32],
+    legend: &'a SemanticTokensLegend,
+) -> orfail::Result<Vec<SemanticToken<'a>>> {
+    data.len().is_multiple_of(5).or_fail_with(|()| {
+        format!(
+            "invalid semantic tokens data: length {} is not a multiple of 5",
+            data.len()
+        )
+    })?;
+
+    let mut tokens = Vec::with_capacity(data.len() / 5);
+    let mut line = 0;
+    let mut character = 0;
+    for chunk in data.chunks_exact(5) {
+        let [delta_line, delta_start, length, token_type, modifiers] =
+            [chunk[0], chunk[1], chunk[2], chunk[3], chunk[4]].map(|n| n as usize);
+        if delta_line > 0 {
+            line += delta_line;
+            character = delta_start;
+        } else {
+            character += delta_start;
+        }
+
+        let token_type = legend
+            .token_types
+            .get(token_type)
+            .or_fail_with(|()| format!("unknown semantic token type index: {token_type}"))?;
+        let modifiers = legend
+            .token_modifiers
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i < usize::BITS as usize && modifiers & (1 << i) != 0)
+            .map(|(_, m)| m.as_str())
+            .collect();
+        tokens.push(SemanticToken {
+            line,
+            character,
+            length,
+            token_type,
+            modifiers,
+        });
+    }
+    Ok(tokens)
+}
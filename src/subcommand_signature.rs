@@ -0,0 +1,235 @@
+use orfail::OrFail;
+
+use crate::{
+    args::RAW_FLAG,
+    json::JsonObject,
+    lsp::{MarkupText, PositionEncoding},
+    proxy_client::{PORT_OPT, ProxyClient},
+    target::{TARGET_ARG, TargetLocation},
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("signature")
+        .doc("Show signature help at a call site (textDocument/signatureHelp)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let target: TargetLocation = TARGET_ARG.take(&mut args).then(|a| a.value().parse())?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    target.file.check_existence().or_fail()?;
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    let text = target.file.read_to_string().or_fail()?;
+    let params = nojson::object(|f| target.fmt_json_object(f, &text, encoding));
+    let result = client
+        .call("textDocument/signatureHelp", params)
+        .or_fail()?;
+
+    if raw {
+        println!("{result}");
+        return Ok(None);
+    }
+
+    let help = Option::<SignatureHelp>::try_from(result.value()).or_fail()?;
+    let Some(help) = help.filter(|h| !h.signatures.is_empty()) else {
+        println!("Not found");
+        return Ok(None);
+    };
+
+    let active_signature = help.active_signature.unwrap_or(0);
+    for (i, signature) in help.signatures.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let active = i == active_signature;
+        let active_parameter = signature
+            .active_parameter
+            .or(help.active_parameter)
+            .filter(|_| active);
+        print_signature(
+            i,
+            help.signatures.len(),
+            signature,
+            active,
+            active_parameter,
+            encoding,
+        );
+    }
+
+    Ok(None)
+}
+
+fn print_signature(
+    index: usize,
+    count: usize,
+    signature: &SignatureInformation,
+    active: bool,
+    active_parameter: Option<usize>,
+    encoding: PositionEncoding,
+) {
+    let marker = if active { " (active)" } else { "" };
+    println!("# Signature {}/{count}{marker}\n", index + 1);
+
+    let offsets = signature.parameter_offsets(encoding);
+    println!("```");
+    println!("{}", signature.label);
+    if let Some((start, end)) = active_parameter.and_then(|i| offsets.get(i).copied().flatten()) {
+        let padding = signature.label[..start].chars().count();
+        let width = signature.label[start..end].chars().count().max(1);
+        println!("{}{}", " ".repeat(padding), "^".repeat(width));
+    }
+    println!("```");
+
+    if let Some(documentation) = &signature.documentation {
+        println!("\n{}", documentation.trim_end());
+    }
+
+    if signature.parameters.is_empty() {
+        return;
+    }
+    println!("\n## Parameters\n");
+    for (i, (parameter, offsets)) in signature.parameters.iter().zip(&offsets).enumerate() {
+        let label = match (&parameter.label, offsets) {
+            (ParameterLabel::String(label), _) => label.as_str(),
+            (ParameterLabel::Offsets(..), Some((start, end))) => &signature.label[*start..*end],
+            (ParameterLabel::Offsets(start, end), None) => {
+                println!("- (invalid label offsets {start}..{end})");
+                continue;
+            }
+        };
+        let marker = if Some(i) == active_parameter {
+            " (active)"
+        } else {
+            ""
+        };
+        match &parameter.documentation {
+            Some(documentation) => {
+                let mut lines = documentation.trim_end().lines();
+                println!("- `{label}`{marker}: {}", lines.next().unwrap_or_default());
+                for line in lines {
+                    println!("  {line}");
+                }
+            }
+            None => println!("- `{label}`{marker}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SignatureHelp {
+    signatures: Vec<SignatureInformation>,
+    active_signature: Option<usize>,
+    active_parameter: Option<usize>,
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for SignatureHelp {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            signatures: object.convert_required("signatures")?,
+            active_signature: object.convert_optional("activeSignature")?,
+            active_parameter: object.convert_optional("activeParameter")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SignatureInformation {
+    label: String,
+    documentation: Option<String>,
+    parameters: Vec<ParameterInformation>,
+    active_parameter: Option<usize>,
+}
+
+impl SignatureInformation {
+    /// Returns the byte range of each parameter within the signature label
+    fn parameter_offsets(&self, encoding: PositionEncoding) -> Vec<Option<(usize, usize)>> {
+        let mut cursor = 0;
+        self.parameters
+            .iter()
+            .map(|parameter| {
+                let (start, end) = match &parameter.label {
+                    ParameterLabel::String(label) => {
+                        let start = cursor + self.label.get(cursor..)?.find(label.as_str())?;
+                        (start, start + label.len())
+                    }
+                    ParameterLabel::Offsets(start, end) => (
+                        encoding.to_byte_offset(&self.label, *start)?,
+                        encoding.to_byte_offset(&self.label, *end)?,
+                    ),
+                };
+                self.label.get(start..end)?;
+                cursor = end;
+                Some((start, end))
+            })
+            .collect()
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for SignatureInformation {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            label: object.convert_required("label")?,
+            documentation: object
+                .convert_optional::<MarkupText>("documentation")?
+                .map(|d| d.0),
+            parameters: object.convert_optional_or_default("parameters")?,
+            active_parameter: object.convert_optional("activeParameter")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ParameterInformation {
+    label: ParameterLabel,
+    documentation: Option<String>,
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for ParameterInformation {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            label: object.convert_required("label")?,
+            documentation: object
+                .convert_optional::<MarkupText>("documentation")?
+                .map(|d| d.0),
+        })
+    }
+}
+
+/// Either a substring of the signature label or `[start, end)` offsets into it
+#[derive(Debug, Clone)]
+enum ParameterLabel {
+    String(String),
+    Offsets(usize, usize),
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for ParameterLabel {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        if value.kind().is_string() {
+            return Ok(Self::String(value.try_into()?));
+        }
+        let [start, end]: [usize; 2] = value.try_into()?;
+        Ok(Self::Offsets(start, end))
+    }
+}
@@ -0,0 +1,98 @@
+use orfail::OrFail;
+
+use crate::{
+    args::RAW_FLAG,
+    json::JsonObject,
+    lsp_server::GET_SERVER_CAPABILITIES_METHOD,
+    proxy_client::{PORT_OPT, ProxyClient},
+    symbol::{WorkspaceSymbol, WorkspaceSymbolLocation},
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("symbols")
+        .doc("Search for symbols in the workspace by name (workspace/symbol)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let query: String = noargs::arg("QUERY")
+        .doc("Symbol name (or a part of it) to search for")
+        .example("main")
+        .take(&mut args)
+        .then(|a| a.value().parse())?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    let params = nojson::object(|f| f.member("query", &query));
+    let result = client.call("workspace/symbol", params).or_fail()?;
+
+    if raw {
+        println!("{result}");
+        return Ok(None);
+    }
+
+    let mut symbols = Option::<Vec<WorkspaceSymbol>>::try_from(result.value())
+        .or_fail()?
+        .unwrap_or_default();
+    if symbols.is_empty() {
+        println!("Not found");
+        return Ok(None);
+    }
+
+    // Symbols without a range are shown at the top of their file if they cannot be resolved
+    if supports_resolve(&mut client).or_fail()? {
+        client.resolve_items("workspaceSymbol/resolve", &mut symbols);
+    }
+
+    let base_dir = std::env::current_dir().or_fail()?;
+    for symbol in symbols {
+        let (uri, position) = match &symbol.location {
+            WorkspaceSymbolLocation::Location(location) => {
+                let text = location.uri.read_to_string().unwrap_or_default();
+                (&location.uri, location.range.start.decode(&text, encoding))
+            }
+            WorkspaceSymbolLocation::Uri(uri) => (uri, Default::default()),
+        };
+        print!(
+            "{}:{}:{}: {} `{}`",
+            uri.relative_path(&base_dir).display(),
+            position.line + 1,
+            position.character + 1,
+            symbol.kind,
+            symbol.name
+        );
+        if let Some(container_name) = symbol.container_name.filter(|s| !s.is_empty()) {
+            print!(" (in `{container_name}`)");
+        }
+        println!();
+    }
+
+    Ok(None)
+}
+
+/// Returns whether `workspaceSymbolProvider.resolveProvider` is enabled in the server capabilities
+fn supports_resolve(client: &mut ProxyClient) -> orfail::Result<bool> {
+    let capabilities = client.call(GET_SERVER_CAPABILITIES_METHOD, ()).or_fail()?;
+    let Some(provider) = JsonObject::new(capabilities.value())
+        .or_fail()?
+        .get_optional("workspaceSymbolProvider")
+    else {
+        return Ok(false);
+    };
+    if provider.kind().is_bool() {
+        return Ok(false);
+    }
+    JsonObject::new(provider)
+        .and_then(|provider| provider.convert_optional_or_default("resolveProvider"))
+        .or_fail()
+}
@@ -0,0 +1,11 @@
+use crate::location;
+
+pub fn try_run(args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    location::try_run_subcommand(
+        args,
+        noargs::cmd("type-definition")
+            .doc("Get type definition location for a symbol (textDocument/typeDefinition)"),
+        "textDocument/typeDefinition",
+        "Type Definition",
+    )
+}
@@ -0,0 +1,111 @@
+use orfail::OrFail;
+
+use crate::{
+    args::RAW_FLAG,
+    proxy_client::{PORT_OPT, ProxyClient},
+    symbol::{HierarchyChild, HierarchyDirection, HierarchyItem, HierarchyTreePrinter},
+    target::{TARGET_ARG, TargetLocation},
+};
+
+pub fn try_run(mut args: noargs::RawArgs) -> noargs::Result<Option<noargs::RawArgs>> {
+    if !noargs::cmd("types")
+        .doc("Show the type hierarchy of a type (typeHierarchy/supertypes, typeHierarchy/subtypes)")
+        .take(&mut args)
+        .is_present()
+    {
+        return Ok(Some(args));
+    }
+
+    let port: u16 = PORT_OPT.take(&mut args).then(|a| a.value().parse())?;
+    let supertypes = noargs::flag("super")
+        .doc("Show the supertypes of the target (default)")
+        .take(&mut args)
+        .is_present();
+    let subtypes = noargs::flag("sub")
+        .short('s')
+        .doc("Show the subtypes of the target")
+        .take(&mut args)
+        .is_present();
+    let depth: usize = noargs::opt("depth")
+        .short('d')
+        .ty("INTEGER")
+        .default("1")
+        .doc("Maximum depth of the type tree")
+        .take(&mut args)
+        .then(|a| a.value().parse())?;
+    let raw = RAW_FLAG.take(&mut args).is_present();
+    let target: TargetLocation = TARGET_ARG.take(&mut args).then(|a| a.value().parse())?;
+
+    if let Some(help) = args.finish()? {
+        print!("{help}");
+        return Ok(None);
+    }
+    (!(supertypes && subtypes))
+        .or_fail_with(|()| "--super and --sub cannot be specified together".to_owned())?;
+    target.file.check_existence().or_fail()?;
+
+    let direction = if subtypes {
+        Direction::Subtypes
+    } else {
+        Direction::Supertypes
+    };
+
+    let mut client = ProxyClient::connect(port).or_fail()?;
+    let encoding = client.position_encoding().or_fail()?;
+
+    let text = target.file.read_to_string().or_fail()?;
+    let params = nojson::object(|f| target.fmt_json_object(f, &text, encoding));
+    let result = client
+        .call("textDocument/prepareTypeHierarchy", params)
+        .or_fail()?;
+    if raw {
+        println!("{result}");
+    }
+
+    let items = Option::<Vec<HierarchyItem>>::try_from(result.value())
+        .or_fail()?
+        .unwrap_or_default();
+    HierarchyTreePrinter::new(client, direction, depth, raw, encoding)
+        .or_fail()?
+        .print(&items)
+        .or_fail()?;
+
+    Ok(None)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Supertypes,
+    Subtypes,
+}
+
+impl HierarchyDirection for Direction {
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Supertypes => "Supertypes",
+            Self::Subtypes => "Subtypes",
+        }
+    }
+
+    fn method(&self) -> &'static str {
+        match self {
+            Self::Supertypes => "typeHierarchy/supertypes",
+            Self::Subtypes => "typeHierarchy/subtypes",
+        }
+    }
+
+    fn parse_children(
+        &self,
+        _parent: &HierarchyItem,
+        result: nojson::RawJsonValue<'_, '_>,
+    ) -> Result<Vec<HierarchyChild>, nojson::JsonParseError> {
+        let items = Option::<Vec<HierarchyItem>>::try_from(result)?.unwrap_or_default();
+        Ok(items
+            .into_iter()
+            .map(|item| HierarchyChild {
+                item,
+                call_sites: Vec::new(),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,407 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use orfail::OrFail;
+
+use crate::{
+    json::JsonObject,
+    location::Location,
+    lsp::{DocumentUri, Position, PositionEncoding, PositionRange},
+    proxy_client::{ProxyClient, Resolvable},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolKind(pub u32);
+
+impl SymbolKind {
+    pub fn name(self) -> &'static str {
+        match self.0 {
+            1 => "File",
+            2 => "Module",
+            3 => "Namespace",
+            4 => "Package",
+            5 => "Class",
+            6 => "Method",
+            7 => "Property",
+            8 => "Field",
+            9 => "Constructor",
+            10 => "Enum",
+            11 => "Interface",
+            12 => "Function",
+            13 => "Variable",
+            14 => "Constant",
+            15 => "String",
+            16 => "Number",
+            17 => "Boolean",
+            18 => "Array",
+            19 => "Object",
+            20 => "Key",
+            21 => "Null",
+            22 => "EnumMember",
+            23 => "Struct",
+            24 => "Event",
+            25 => "Operator",
+            26 => "TypeParameter",
+            _ => "Unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for SymbolKind {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        Ok(Self(value.try_into()?))
+    }
+}
+
+/// `SymbolInformation` or `WorkspaceSymbol` returned by `workspace/symbol`
+#[derive(Debug, Clone)]
+pub struct WorkspaceSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub container_name: Option<String>,
+    pub location: WorkspaceSymbolLocation,
+    pub json: nojson::RawJsonOwned,
+}
+
+impl Resolvable for WorkspaceSymbol {
+    fn is_resolved(&self) -> bool {
+        matches!(self.location, WorkspaceSymbolLocation::Location(_))
+    }
+
+    fn json(&self) -> &nojson::RawJsonOwned {
+        &self.json
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for WorkspaceSymbol {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            name: object.convert_required("name")?,
+            kind: object.convert_required("kind")?,
+            container_name: object.convert_optional("containerName")?,
+            location: object.convert_required("location")?,
+            json: value.extract().into_owned(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum WorkspaceSymbolLocation {
+    Location(Location),
+
+    /// Location without a range, which needs to be resolved by `workspaceSymbol/resolve`
+    Uri(DocumentUri),
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for WorkspaceSymbolLocation {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        if object.get_optional("range").is_some() {
+            Ok(Self::Location(value.try_into()?))
+        } else {
+            Ok(Self::Uri(object.convert_required("uri")?))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentSymbol {
+    pub name: String,
+    pub detail: Option<String>,
+    pub kind: SymbolKind,
+    pub range: PositionRange,
+    pub selection_range: PositionRange,
+    pub children: Vec<DocumentSymbol>,
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for DocumentSymbol {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            name: object.convert_required("name")?,
+            detail: object.convert_optional("detail")?,
+            kind: object.convert_required("kind")?,
+            range: object.convert_required("range")?,
+            selection_range: object.convert_required("selectionRange")?,
+            children: object.convert_optional_or_default("children")?,
+        })
+    }
+}
+
+/// Result of `textDocument/documentSymbol`
+#[derive(Debug, Clone)]
+pub enum DocumentSymbols {
+    Hierarchical(Vec<DocumentSymbol>),
+
+    /// `SymbolInformation[]` returned by servers without hierarchical symbol support
+    Flat(Vec<WorkspaceSymbol>),
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for DocumentSymbols {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        if value.kind().is_null() {
+            return Ok(Self::Hierarchical(Vec::new()));
+        }
+
+        let is_flat = match value.to_array()?.next() {
+            Some(first) => first.to_member("location")?.get().is_some(),
+            None => false,
+        };
+        if is_flat {
+            Ok(Self::Flat(value.try_into()?))
+        } else {
+            Ok(Self::Hierarchical(value.try_into()?))
+        }
+    }
+}
+
+/// `CallHierarchyItem` or `TypeHierarchyItem`
+///
+/// The original JSON is kept as-is because the item must be sent back verbatim
+/// (including the `data` member) in follow-up requests.
+#[derive(Debug, Clone)]
+pub struct HierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub detail: Option<String>,
+    pub uri: DocumentUri,
+    pub range: PositionRange,
+    pub selection_range: PositionRange,
+    pub json: nojson::RawJsonOwned,
+}
+
+impl HierarchyItem {
+    /// Key identifying the item for cycle detection
+    pub fn key(&self) -> (DocumentUri, Position, String) {
+        (
+            self.uri.clone(),
+            self.selection_range.start,
+            self.name.clone(),
+        )
+    }
+}
+
+impl<'text, 'raw> TryFrom<nojson::RawJsonValue<'text, 'raw>> for HierarchyItem {
+    type Error = nojson::JsonParseError;
+
+    fn try_from(value: nojson::RawJsonValue<'text, 'raw>) -> Result<Self, Self::Error> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            name: object.convert_required("name")?,
+            kind: object.convert_required("kind")?,
+            detail: object.convert_optional("detail")?,
+            uri: object.convert_required("uri")?,
+            range: object.convert_required("range")?,
+            selection_range: object.convert_required("selectionRange")?,
+            json: value.extract().into_owned(),
+        })
+    }
+}
+
+/// Child of a [`HierarchyItem`] in a call or type hierarchy
+#[derive(Debug, Clone)]
+pub struct HierarchyChild {
+    pub item: HierarchyItem,
+
+    /// Locations where the call is made (empty for type hierarchies)
+    pub call_sites: Vec<Location>,
+}
+
+/// Direction in which a hierarchy tree is expanded (e.g. incoming calls or supertypes)
+pub trait HierarchyDirection {
+    /// Heading of the tree, such as "Incoming calls"
+    fn title(&self) -> &'static str;
+
+    /// Method returning the children of an item
+    fn method(&self) -> &'static str;
+
+    /// Parses the result of [`Self::method`] for `parent`
+    fn parse_children(
+        &self,
+        parent: &HierarchyItem,
+        result: nojson::RawJsonValue<'_, '_>,
+    ) -> Result<Vec<HierarchyChild>, nojson::JsonParseError>;
+}
+
+/// Prints hierarchy trees by recursively requesting the children of each item
+#[derive(Debug)]
+pub struct HierarchyTreePrinter<D> {
+    client: ProxyClient,
+    direction: D,
+    max_depth: usize,
+    raw: bool,
+    base_dir: PathBuf,
+    encoding: PositionEncoding,
+    texts: HashMap<DocumentUri, String>,
+    ancestors: Vec<(DocumentUri, Position, String)>,
+    expanded: HashSet<(DocumentUri, Position, String)>,
+}
+
+impl<D: HierarchyDirection> HierarchyTreePrinter<D> {
+    pub fn new(
+        client: ProxyClient,
+        direction: D,
+        max_depth: usize,
+        raw: bool,
+        encoding: PositionEncoding,
+    ) -> orfail::Result<Self> {
+        Ok(Self {
+            client,
+            direction,
+            max_depth,
+            raw,
+            base_dir: std::env::current_dir().or_fail()?,
+            encoding,
+            texts: HashMap::new(),
+            ancestors: Vec::new(),
+            expanded: HashSet::new(),
+        })
+    }
+
+    /// Prints the trees rooted at the items returned by the prepare request
+    pub fn print(&mut self, items: &[HierarchyItem]) -> orfail::Result<()> {
+        if items.is_empty() {
+            if !self.raw {
+                println!("Not found");
+            }
+            return Ok(());
+        }
+
+        for (i, item) in items.iter().enumerate() {
+            if !self.raw {
+                if i > 0 {
+                    println!();
+                }
+                println!(
+                    "# {}: {} `{}` ({})\n",
+                    self.direction.title(),
+                    item.kind,
+                    item.name,
+                    self.fmt_position(&item.uri, item.selection_range.start)
+                );
+            }
+            let printed = self.print_children(item, 0).or_fail()?;
+            if !self.raw && !printed {
+                println!("No {}", self.direction.title().to_lowercase());
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints the children of `item` recursively and returns whether any child was found
+    fn print_children(&mut self, item: &HierarchyItem, depth: usize) -> orfail::Result<bool> {
+        if depth >= self.max_depth {
+            return Ok(false);
+        }
+
+        let params = nojson::object(|f| f.member("item", &item.json));
+        let result = self
+            .client
+            .call(self.direction.method(), params)
+            .or_fail()?;
+        if self.raw {
+            println!("{result}");
+        }
+        let children = self
+            .direction
+            .parse_children(item, result.value())
+            .or_fail()?;
+
+        let key = item.key();
+        self.ancestors.push(key.clone());
+        self.expanded.insert(key);
+        for HierarchyChild {
+            item: child,
+            call_sites,
+        } in &children
+        {
+            let child_key = child.key();
+            let note = if self.ancestors.contains(&child_key) {
+                Some("cycle")
+            } else if self.expanded.contains(&child_key) {
+                Some("see above")
+            } else {
+                None
+            };
+
+            if !self.raw {
+                let call_sites = call_sites
+                    .iter()
+                    .map(|site| self.fmt_range(&site.uri, site.range))
+                    .collect::<Vec<_>>();
+
+                print!("{}- {} `{}`", "  ".repeat(depth), child.kind, child.name);
+                if let Some(detail) = &child.detail {
+                    print!(": `{detail}`");
+                }
+                print!(
+                    " ({})",
+                    self.fmt_position(&child.uri, child.selection_range.start)
+                );
+                if !call_sites.is_empty() {
+                    print!(" [called at {}]", call_sites.join(", "));
+                }
+                match note {
+                    Some(note) => println!(" ({note})"),
+                    None => println!(),
+                }
+            }
+
+            if note.is_none() {
+                self.print_children(child, depth + 1).or_fail()?;
+            }
+        }
+        self.ancestors.pop();
+
+        Ok(!children.is_empty())
+    }
+
+    fn fmt_position(&mut self, uri: &DocumentUri, position: Position) -> String {
+        let encoding = self.encoding;
+        let position = position.decode(self.text(uri), encoding);
+        format!(
+            "{}:{}:{}",
+            uri.relative_path(&self.base_dir).display(),
+            position.line + 1,
+            position.character + 1
+        )
+    }
+
+    fn fmt_range(&mut self, uri: &DocumentUri, range: PositionRange) -> String {
+        let encoding = self.encoding;
+        let range = range.decode(self.text(uri), encoding);
+        format!(
+            "{}:{}:{}-{}:{}",
+            uri.relative_path(&self.base_dir).display(),
+            range.start.line + 1,
+            range.start.character + 1,
+            range.end.line + 1,
+            range.end.character + 1
+        )
+    }
+
+    fn text(&mut self, uri: &DocumentUri) -> &str {
+        self.texts
+            .entry(uri.clone())
+            .or_insert_with(|| uri.read_to_string().unwrap_or_default())
+    }
+}
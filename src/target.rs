@@ -1,11 +1,15 @@
 use std::num::NonZeroUsize;
 
-use crate::lsp::DocumentUri;
+use crate::lsp::{DocumentUri, Position, PositionEncoding, PositionRange};
 
 pub const TARGET_ARG: noargs::ArgSpec = noargs::arg("TARGET")
     .example("/path/to/file:1:5")
     .doc("Target location (FILE:LINE:CHAR)");
 
+pub const FILE_ARG: noargs::ArgSpec = noargs::arg("FILE")
+    .example("/path/to/file")
+    .doc("Target file");
+
 #[derive(Debug, Clone)]
 pub struct TargetLocation {
     pub file: DocumentUri,
@@ -14,21 +18,26 @@ pub struct TargetLocation {
 }
 
 impl TargetLocation {
+    /// Returns the 0-based position counting `encoding` units (`text` is the content of the file)
+    pub fn to_position(&self, text: &str, encoding: PositionEncoding) -> Position {
+        Position {
+            line: self.line.get() - 1,
+            character: self.character.get() - 1,
+        }
+        .encode(text, encoding)
+    }
+
     pub fn fmt_json_object(
         &self,
         f: &mut nojson::JsonObjectFormatter<'_, '_, '_>,
+        text: &str,
+        encoding: PositionEncoding,
     ) -> std::fmt::Result {
         f.member(
             "textDocument",
             nojson::object(|f| f.member("uri", &self.file)),
         )?;
-        f.member(
-            "position",
-            nojson::object(|f| {
-                f.member("line", self.line.get() - 1)?;
-                f.member("character", self.character.get() - 1)
-            }),
-        )?;
+        f.member("position", self.to_position(text, encoding))?;
         Ok(())
     }
 }
@@ -60,3 +69,124 @@ impl std::str::FromStr for TargetLocation {
         })
     }
 }
+
+pub const TARGET_RANGE_ARG: noargs::ArgSpec = noargs::arg("TARGET")
+    .example("/path/to/file:1:5-3:1")
+    .doc("Target file with an optional range (FILE[:LINE:CHAR-LINE:CHAR])");
+
+#[derive(Debug, Clone)]
+pub struct TargetRange {
+    pub file: DocumentUri,
+    pub range: Option<PositionRange>,
+}
+
+impl std::str::FromStr for TargetRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (file, range) = match s.split_once(':') {
+            Some((file, range)) => (file, Some(range)),
+            None => (s, None),
+        };
+        let file = DocumentUri::new(file)
+            .map_err(|e| format!("invalid file path '{file}': {}", e.message))?;
+
+        let range = range
+            .map(|range| {
+                let (start, end) = range.split_once('-').ok_or_else(|| {
+                    format!("invalid range '{range}': expected LINE:CHAR-LINE:CHAR")
+                })?;
+                Ok::<_, String>(PositionRange {
+                    start: parse_position(start)?,
+                    end: parse_position(end)?,
+                })
+            })
+            .transpose()?;
+
+        Ok(Self { file, range })
+    }
+}
+
+/// Parses a 1-based `LINE:CHAR` string into a 0-based position
+fn parse_position(s: &str) -> Result<Position, String> {
+    let (line_str, character_str) = s
+        .split_once(':')
+        .ok_or_else(|| format!("invalid position '{s}': expected LINE:CHAR"))?;
+    let line = line_str
+        .parse::<NonZeroUsize>()
+        .map_err(|_| format!("invalid line number '{line_str}': must be a positive integer"))?;
+    let character = character_str.parse::<NonZeroUsize>().map_err(|_| {
+        format!("invalid column number '{character_str}': must be a positive integer")
+    })?;
+    Ok(Position {
+        line: line.get() - 1,
+        character: character.get() - 1,
+    })
+}
+
+pub const TARGET_LINES_ARG: noargs::ArgSpec = noargs::arg("TARGET")
+    .example("/path/to/file:10-20")
+    .doc("Target file with an optional line range (FILE[:LINE-LINE])");
+
+#[derive(Debug, Clone)]
+pub struct TargetLines {
+    pub file: DocumentUri,
+    pub lines: Option<(NonZeroUsize, NonZeroUsize)>,
+}
+
+impl TargetLines {
+    /// Returns the 0-based range covering the target lines (or the whole `text`)
+    pub fn to_range(&self, text: &str) -> PositionRange {
+        match self.lines {
+            Some((start, end)) => PositionRange {
+                start: Position {
+                    line: start.get() - 1,
+                    character: 0,
+                },
+                end: Position {
+                    line: end.get(),
+                    character: 0,
+                },
+            },
+            None => PositionRange {
+                start: Position::default(),
+                end: Position {
+                    line: text.lines().count(),
+                    character: 0,
+                },
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for TargetLines {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (file, lines) = match s.split_once(':') {
+            Some((file, lines)) => (file, Some(lines)),
+            None => (s, None),
+        };
+        let file = DocumentUri::new(file)
+            .map_err(|e| format!("invalid file path '{file}': {}", e.message))?;
+
+        let lines = lines
+            .map(|lines| {
+                let (start, end) = lines.split_once('-').unwrap_or((lines, lines));
+                let start = parse_line(start)?;
+                let end = parse_line(end)?;
+                if end < start {
+                    return Err(format!("invalid line range '{lines}': end is before start"));
+                }
+                Ok((start, end))
+            })
+            .transpose()?;
+
+        Ok(Self { file, lines })
+    }
+}
+
+fn parse_line(s: &str) -> Result<NonZeroUsize, String> {
+    s.parse::<NonZeroUsize>()
+        .map_err(|_| format!("invalid line number '{s}': must be a positive integer"))
+}